@@ -0,0 +1,16 @@
+#![no_main]
+#![allow(dead_code)]
+
+include!("../../src/bin/04.rs");
+
+// Away from the `Ok` function the day imports from anyhow, which fuzz_target! cannot match on.
+mod target {
+    use super::{parse, part1, part2};
+
+    libfuzzer_sys::fuzz_target!(|input: &str| {
+        if let Ok(input) = parse(input) {
+            let _ = part1(&input);
+            let _ = part2(&input);
+        }
+    });
+}
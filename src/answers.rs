@@ -0,0 +1,357 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// Like the site, a wrong guess locks the part for a minute, and for five once there were a few.
+pub const COOLDOWN_SECS: u64 = 60;
+pub const LONG_COOLDOWN_SECS: u64 = 300;
+pub const LONG_COOLDOWN_AFTER: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Feedback {
+    TooHigh,
+    TooLow,
+    Wrong,
+    Correct,
+    // Guessed before the correct answer was known, and without saying what the site replied.
+    Unknown,
+}
+
+impl FromStr for Feedback {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "too-high" => Ok(Feedback::TooHigh),
+            "too-low" => Ok(Feedback::TooLow),
+            "wrong" => Ok(Feedback::Wrong),
+            "correct" => Ok(Feedback::Correct),
+            _ => bail!("Unknown feedback [{}]", s),
+        }
+    }
+}
+
+impl fmt::Display for Feedback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Feedback::TooHigh => "too high",
+            Feedback::TooLow => "too low",
+            Feedback::Wrong => "wrong",
+            Feedback::Correct => "correct",
+            Feedback::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Guess {
+    pub answer: String,
+    // Seconds since the Unix epoch.
+    pub submitted_at: u64,
+    pub feedback: Feedback,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartLog {
+    pub day: String,
+    pub part: u8,
+    pub correct: Option<String>,
+    pub guesses: Vec<Guess>,
+}
+
+impl PartLog {
+    fn new(day: &str, part: u8) -> Self {
+        Self {
+            day: day.to_owned(),
+            part,
+            correct: None,
+            guesses: vec![],
+        }
+    }
+
+    // How the site would reply to a guess, once the correct answer is known.
+    pub fn judge(&self, answer: &str) -> Option<Feedback> {
+        let correct = self.correct.as_deref()?;
+        if answer == correct {
+            return Some(Feedback::Correct);
+        }
+
+        Some(match (answer.parse::<i128>(), correct.parse::<i128>()) {
+            (std::result::Result::Ok(guess), std::result::Result::Ok(correct))
+                if guess > correct =>
+            {
+                Feedback::TooHigh
+            }
+            (std::result::Result::Ok(_), std::result::Result::Ok(_)) => Feedback::TooLow,
+            _ => Feedback::Wrong,
+        })
+    }
+
+    // Smallest answer that was too high and largest that was too low.
+    pub fn bounds(&self) -> (Option<i128>, Option<i128>) {
+        let numbers = |feedback: Feedback| {
+            self.guesses
+                .iter()
+                .filter(move |guess| guess.feedback == feedback)
+                .filter_map(|guess| guess.answer.parse::<i128>().ok())
+        };
+
+        (
+            numbers(Feedback::TooHigh).min(),
+            numbers(Feedback::TooLow).max(),
+        )
+    }
+
+    // When the part can be guessed again, if it's still locked by a wrong guess at `now`.
+    pub fn locked_until(&self, now: u64) -> Option<u64> {
+        let last = self.guesses.last()?;
+        if last.feedback == Feedback::Correct {
+            return None;
+        }
+
+        let wrong = self
+            .guesses
+            .iter()
+            .filter(|guess| guess.feedback != Feedback::Correct)
+            .count();
+        let cooldown = if wrong > LONG_COOLDOWN_AFTER {
+            LONG_COOLDOWN_SECS
+        } else {
+            COOLDOWN_SECS
+        };
+
+        Some(last.submitted_at + cooldown).filter(|until| *until > now)
+    }
+
+    // Why a guess can't be right, given the replies to the earlier ones.
+    pub fn contradictions(&self, answer: &str) -> Vec<String> {
+        let mut warnings = vec![];
+
+        if let Some(guess) = self
+            .guesses
+            .iter()
+            .find(|guess| guess.answer == answer && guess.feedback != Feedback::Unknown)
+        {
+            warnings.push(format!(
+                "{} was already guessed and was {}",
+                answer, guess.feedback
+            ));
+        }
+
+        if let std::result::Result::Ok(number) = answer.parse::<i128>() {
+            let (high, low) = self.bounds();
+            if let Some(high) = high.filter(|high| number >= *high) {
+                warnings.push(format!(
+                    "{} is not below {}, which was too high",
+                    answer, high
+                ));
+            }
+            if let Some(low) = low.filter(|low| number <= *low) {
+                warnings.push(format!(
+                    "{} is not above {}, which was too low",
+                    answer, low
+                ));
+            }
+        }
+
+        warnings
+    }
+
+    // Also settles the guesses made before it was known.
+    fn set_correct(&mut self, answer: &str) {
+        self.correct = Some(answer.to_owned());
+        for index in 0..self.guesses.len() {
+            if self.guesses[index].feedback == Feedback::Unknown {
+                self.guesses[index].feedback = self
+                    .judge(&self.guesses[index].answer)
+                    .unwrap_or(Feedback::Unknown);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    pub feedback: Feedback,
+    pub warnings: Vec<String>,
+}
+
+// Every guess at every part, with the correct answers once known.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnswerLog {
+    pub parts: Vec<PartLog>,
+}
+
+impl AnswerLog {
+    // Empty until the first guess is saved.
+    pub fn load(path: &Path) -> Result<AnswerLog> {
+        if !path.exists() {
+            return Ok(AnswerLog::default());
+        }
+
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read answer log {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid answer log {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn part(&self, day: &str, part: u8) -> Option<&PartLog> {
+        self.parts
+            .iter()
+            .find(|log| log.day == day && log.part == part)
+    }
+
+    fn part_mut(&mut self, day: &str, part: u8) -> &mut PartLog {
+        let position = match self
+            .parts
+            .iter()
+            .position(|log| log.day == day && log.part == part)
+        {
+            Some(position) => position,
+            None => {
+                self.parts.push(PartLog::new(day, part));
+                self.parts.len() - 1
+            }
+        };
+
+        &mut self.parts[position]
+    }
+
+    // Records a guess at `now`. The reply comes from the correct answer when it's known, otherwise
+    // from `feedback`, which is what the site said.
+    pub fn submit(
+        &mut self,
+        day: &str,
+        part: u8,
+        answer: &str,
+        feedback: Option<Feedback>,
+        now: u64,
+    ) -> Result<Submission> {
+        let log = self.part(day, part);
+
+        if let Some(until) = log.and_then(|log| log.locked_until(now)) {
+            bail!(
+                "Day {} part {} is locked for another {}s after a wrong guess",
+                day,
+                part,
+                until - now
+            );
+        }
+
+        let warnings = log
+            .map(|log| log.contradictions(answer))
+            .unwrap_or_default();
+        let feedback = match (log.and_then(|log| log.judge(answer)), feedback) {
+            (Some(judged), Some(given)) if judged != given => bail!(
+                "{} is {}, not {}, since the correct answer is known",
+                answer,
+                judged,
+                given
+            ),
+            (Some(judged), _) => judged,
+            (None, given) => given.unwrap_or(Feedback::Unknown),
+        };
+
+        let log = self.part_mut(day, part);
+        log.guesses.push(Guess {
+            answer: answer.to_owned(),
+            submitted_at: now,
+            feedback,
+        });
+        if feedback == Feedback::Correct {
+            log.set_correct(answer);
+        }
+
+        Ok(Submission { feedback, warnings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earlier_guesses_are_judged_once_the_answer_is_known() {
+        let mut log = AnswerLog::default();
+        log.submit("01", 1, "50", None, 0).unwrap();
+        log.submit("01", 1, "abc", None, 100).unwrap();
+        log.submit("01", 1, "42", Some(Feedback::Correct), 200)
+            .unwrap();
+
+        let feedback: Vec<Feedback> = log
+            .part("01", 1)
+            .unwrap()
+            .guesses
+            .iter()
+            .map(|guess| guess.feedback)
+            .collect();
+        assert_eq!(
+            vec![Feedback::TooHigh, Feedback::Wrong, Feedback::Correct],
+            feedback
+        );
+        assert_eq!(
+            Feedback::TooLow,
+            log.submit("01", 1, "7", None, 300).unwrap().feedback
+        );
+    }
+
+    #[test]
+    fn guesses_outside_the_bounds_are_warned_about() {
+        let mut log = AnswerLog::default();
+        log.submit("01", 1, "100", Some(Feedback::TooHigh), 0)
+            .unwrap();
+        log.submit("01", 1, "10", Some(Feedback::TooLow), 100)
+            .unwrap();
+
+        assert!(log.part("01", 1).unwrap().contradictions("50").is_empty());
+        assert_eq!(
+            vec![
+                "10 was already guessed and was too low",
+                "10 is not above 10, which was too low"
+            ],
+            log.part("01", 1).unwrap().contradictions("10")
+        );
+        assert_eq!(1, log.part("01", 1).unwrap().contradictions("150").len());
+    }
+
+    #[test]
+    fn wrong_guesses_lock_the_part() {
+        let mut log = AnswerLog::default();
+        log.submit("01", 2, "1", Some(Feedback::Wrong), 1000)
+            .unwrap();
+        assert!(log.submit("01", 2, "2", None, 1059).is_err());
+
+        for (index, now) in [1060, 1120, 1180, 1240].into_iter().enumerate() {
+            log.submit("01", 2, &(index + 2).to_string(), None, now)
+                .unwrap();
+        }
+        assert_eq!(
+            Some(1240 + LONG_COOLDOWN_SECS),
+            log.part("01", 2).unwrap().locked_until(1300)
+        );
+        // Other parts are not locked.
+        log.submit("01", 1, "1", None, 1300).unwrap();
+    }
+
+    #[test]
+    fn rejected_guesses_are_not_logged() {
+        let mut log = AnswerLog::default();
+        log.submit("01", 1, "42", Some(Feedback::Correct), 0)
+            .unwrap();
+        let before = log.clone();
+
+        assert!(log
+            .submit("01", 1, "7", Some(Feedback::TooHigh), 100)
+            .is_err());
+        assert_eq!(before, log);
+    }
+}
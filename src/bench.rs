@@ -0,0 +1,366 @@
+use crate::input_path;
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Days print their measurements on lines starting with this, among the rest of their output.
+pub const MEASUREMENT_PREFIX: &str = "BENCH ";
+// Same for the outcome of each part, when started with `--report`.
+pub const REPORT_PREFIX: &str = "REPORT ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchOptions {
+    pub runs: usize,
+    pub warmup: usize,
+}
+
+impl BenchOptions {
+    // Reads `--bench <runs>` and `--warmup <runs>`, ignoring any other argument.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Option<BenchOptions>> {
+        let args: Vec<String> = args.collect();
+        let value = |name: &str| -> Result<Option<usize>> {
+            match args.iter().position(|arg| arg == name) {
+                None => Ok(None),
+                Some(position) => {
+                    let value = args
+                        .get(position + 1)
+                        .with_context(|| format!("Missing value for {}", name))?;
+                    let value = value
+                        .parse()
+                        .with_context(|| format!("Invalid value for {} [{}]", name, value))?;
+                    Ok(Some(value))
+                }
+            }
+        };
+
+        let Some(runs) = value("--bench")? else {
+            return Ok(None);
+        };
+        ensure!(runs > 0, "At least one run is needed");
+
+        Ok(Some(BenchOptions {
+            runs,
+            warmup: value("--warmup")?.unwrap_or(3),
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub runs: usize,
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub p95_ns: u64,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Result<Stats> {
+        ensure!(!samples.is_empty(), "No samples to compute stats from");
+        samples.sort_unstable();
+
+        // Nearest rank: the smallest sample with at least `percent` of the samples at or below it.
+        let percentile = |percent: usize| {
+            let rank = (samples.len() * percent).div_ceil(100).max(1);
+            samples[rank - 1].as_nanos() as u64
+        };
+
+        Ok(Stats {
+            runs: samples.len(),
+            min_ns: samples[0].as_nanos() as u64,
+            median_ns: percentile(50),
+            p95_ns: percentile(95),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Parse,
+    Solve,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub day: String,
+    pub part: u8,
+    pub parse: Stats,
+    pub solve: Stats,
+}
+
+impl Measurement {
+    pub fn stats(&self, phase: Phase) -> &Stats {
+        match phase {
+            Phase::Parse => &self.parse,
+            Phase::Solve => &self.solve,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    pub warmup: usize,
+    pub measurements: Vec<Measurement>,
+}
+
+impl Report {
+    pub fn load(path: &Path) -> Result<Report> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read bench report {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid bench report {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Phases whose median got slower by more than `threshold` percent since the baseline. Parts
+    // missing from either report are not compared.
+    pub fn regressions(&self, baseline: &Report, threshold: f64) -> Vec<Regression> {
+        let mut regressions = vec![];
+        for current in self.measurements.iter() {
+            let Some(before) = baseline
+                .measurements
+                .iter()
+                .find(|m| m.day == current.day && m.part == current.part)
+            else {
+                continue;
+            };
+
+            for phase in [Phase::Parse, Phase::Solve] {
+                let baseline_ns = before.stats(phase).median_ns;
+                let current_ns = current.stats(phase).median_ns;
+                let change = (current_ns as f64 / baseline_ns.max(1) as f64 - 1.0) * 100.0;
+
+                if change > threshold {
+                    regressions.push(Regression {
+                        day: current.day.clone(),
+                        part: current.part,
+                        phase,
+                        baseline_ns,
+                        current_ns,
+                        change,
+                    });
+                }
+            }
+        }
+
+        regressions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub day: String,
+    pub part: u8,
+    pub phase: Phase,
+    pub baseline_ns: u64,
+    pub current_ns: u64,
+    // In percent of the baseline median.
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Error,
+    Skipped,
+}
+
+// The answer to one part with how long it took, as the runner prints it with `--format json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartReport {
+    pub day: String,
+    pub part: u8,
+    pub status: Status,
+    pub answer: Option<String>,
+    pub parse_ns: Option<u64>,
+    pub solve_ns: Option<u64>,
+    pub input_hash: Option<String>,
+    // Why the part failed or was skipped.
+    pub message: Option<String>,
+}
+
+impl PartReport {
+    pub fn new(day: &str, part: u8, status: Status, message: &str) -> PartReport {
+        PartReport {
+            day: day.to_owned(),
+            part,
+            status,
+            answer: None,
+            parse_ns: None,
+            solve_ns: None,
+            input_hash: None,
+            message: Some(message.to_owned()),
+        }
+    }
+}
+
+// FNV-1a, to tell which input an answer belongs to without sharing the input itself.
+pub fn input_hash(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+pub fn measure<T>(options: &BenchOptions, mut run: impl FnMut() -> Result<T>) -> Result<Stats> {
+    for _ in 0..options.warmup {
+        run()?;
+    }
+
+    let mut samples = Vec::with_capacity(options.runs);
+    for _ in 0..options.runs {
+        let start = Instant::now();
+        std::hint::black_box(run()?);
+        samples.push(start.elapsed());
+    }
+
+    Stats::from_samples(samples)
+}
+
+// Whether the day was started by the runner, with `--bench <runs>` or `--report`. Days check this
+// first thing, so that a slow or failing solve further down can't keep the runner waiting.
+pub fn bench_requested() -> bool {
+    std::env::args().any(|arg| arg == "--bench" || arg == "--report")
+}
+
+// Does nothing unless `bench_requested`. Then times parsing the puzzle input, read once up front,
+// and solving the part from the parsed input, and prints the measurement or the answer for the
+// runner to collect.
+pub fn bench<I, T: Display>(
+    day: &str,
+    part: u8,
+    parse: impl Fn(&str) -> Result<I>,
+    solve: impl Fn(&I) -> Result<T>,
+) -> Result<()> {
+    if std::env::args().any(|arg| arg == "--report") {
+        return report(day, part, parse, solve);
+    }
+
+    let Some(options) = BenchOptions::from_args(std::env::args().skip(1))? else {
+        return Ok(());
+    };
+
+    let input = fs::read_to_string(input_path(day))?;
+    let parse_stats = measure(&options, || parse(&input))?;
+    let parsed = parse(&input)?;
+    let solve_stats = measure(&options, || solve(&parsed))?;
+
+    let measurement = Measurement {
+        day: day.to_owned(),
+        part,
+        parse: parse_stats,
+        solve: solve_stats,
+    };
+    println!(
+        "{}{}",
+        MEASUREMENT_PREFIX,
+        serde_json::to_string(&measurement)?
+    );
+
+    Ok(())
+}
+
+// Solves the part once. Failures end up in the report instead of stopping the day.
+fn report<I, T: Display>(
+    day: &str,
+    part: u8,
+    parse: impl Fn(&str) -> Result<I>,
+    solve: impl Fn(&I) -> Result<T>,
+) -> Result<()> {
+    let input = fs::read_to_string(input_path(day))?;
+    let mut report = PartReport {
+        day: day.to_owned(),
+        part,
+        status: Status::Ok,
+        answer: None,
+        parse_ns: None,
+        solve_ns: None,
+        input_hash: Some(input_hash(&input)),
+        message: None,
+    };
+
+    let start = Instant::now();
+    let parsed = parse(&input);
+    report.parse_ns = Some(start.elapsed().as_nanos() as u64);
+
+    let answer = parsed.and_then(|parsed| {
+        let start = Instant::now();
+        let answer = solve(&parsed);
+        report.solve_ns = Some(start.elapsed().as_nanos() as u64);
+        answer
+    });
+    if let Err(error) = &answer {
+        report.status = Status::Error;
+        report.message = Some(format!("{:#}", error));
+    }
+    report.answer = answer.ok().map(|answer| answer.to_string());
+
+    println!("{}{}", REPORT_PREFIX, serde_json::to_string(&report)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_hash_is_fnv1a() {
+        assert_eq!("cbf29ce484222325", input_hash(""));
+        assert_eq!("af63dc4c8601ec8c", input_hash("a"));
+    }
+
+    #[test]
+    fn stats_use_nearest_rank() {
+        let samples = (1..=20).rev().map(Duration::from_nanos).collect();
+        let stats = Stats::from_samples(samples).unwrap();
+        assert_eq!(
+            (20, 1, 10, 19),
+            (stats.runs, stats.min_ns, stats.median_ns, stats.p95_ns)
+        );
+    }
+
+    #[test]
+    fn regressions_above_threshold() {
+        let stats = |median_ns: u64| Stats {
+            runs: 1,
+            min_ns: median_ns,
+            median_ns,
+            p95_ns: median_ns,
+        };
+        let report = |medians: &[(u8, u64, u64)]| Report {
+            warmup: 0,
+            measurements: medians
+                .iter()
+                .map(|(part, parse_ns, solve_ns)| Measurement {
+                    day: "06".to_owned(),
+                    part: *part,
+                    parse: stats(*parse_ns),
+                    solve: stats(*solve_ns),
+                })
+                .collect(),
+        };
+
+        let baseline = report(&[(1, 100, 100), (2, 100, 100)]);
+        let current = report(&[(1, 109, 100), (2, 100, 150)]);
+        let regressions = current.regressions(&baseline, 10.0);
+        assert_eq!(1, regressions.len());
+        assert_eq!(
+            (2, Phase::Solve, 100, 150),
+            (
+                regressions[0].part,
+                regressions[0].phase,
+                regressions[0].baseline_ns,
+                regressions[0].current_ns
+            )
+        );
+    }
+}
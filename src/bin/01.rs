@@ -1,9 +1,11 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufRead;
 
 const DAY: &str = "01";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -17,72 +19,172 @@ const TEST: &str = "\
 3   3
 ";
 
+fn parse(input: &str) -> Result<LocationLists> {
+    LocationLists::new(input.as_bytes())
+}
+
+fn part1(lists: &LocationLists) -> Result<usize> {
+    Ok(usize::try_from(lists.distance(Metric::Absolute)?)?)
+}
+
+fn part2(lists: &LocationLists) -> Result<usize> {
+    usize::try_from(lists.similarity()?).context("Similarity score is negative")
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut left = vec![];
-        let mut right = vec![];
-
-        for line in reader.lines() {
-            let read_line = line.unwrap();
-            let mut items = read_line.split_whitespace();
-            left.push(items.next().unwrap().parse::<i32>().unwrap());
-            right.push(items.next().unwrap().parse::<i32>().unwrap());
-        }
+    assert_eq!(11, part1(&parse(TEST)?)?);
 
-        left.sort();
-        right.sort();
+    let lists = parse(TEST)?;
+    assert_eq!(35, lists.distance(Metric::Squared)?);
+    assert!(parse("3   4\n4\n").is_err());
+    assert!(parse("3   x\n").is_err());
+    assert!(parse("3   4   5\n").is_err());
 
-        let result: i32 = std::iter::zip(left, right)
-            .map(|(l, r)| (l - r).abs())
-            .sum();
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
 
-        Ok(result as usize)
-    }
+    //region Part 2
+    println!("\n=== Part 2 ===");
 
-    assert_eq!(11, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(31, part2(&parse(TEST)?)?);
+    assert_eq!(lists.similarity()?, lists.similarity_scanning()?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
-    //region Part 2
-    println!("\n=== Part 2 ===");
+    Ok(())
+}
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
+#[derive(Debug, Clone, Copy)]
+enum Metric {
+    Absolute,
+    Squared,
+}
+
+impl Metric {
+    fn distance(&self, a: i64, b: i64) -> Option<i64> {
+        let difference = a.checked_sub(b)?;
+        match self {
+            Metric::Absolute => difference.checked_abs(),
+            Metric::Squared => difference.checked_mul(difference),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LocationLists {
+    left: Vec<i64>,
+    right: Vec<i64>,
+}
+
+impl LocationLists {
+    // Splits each line into its left and right IDs and collects both columns, naming the line
+    // that is not a pair of IDs.
+    fn new<R: BufRead>(reader: R) -> Result<LocationLists> {
         let mut left = vec![];
         let mut right = vec![];
 
-        for line in reader.lines() {
-            let read_line = line.unwrap();
-            let mut items = read_line.split_whitespace();
-            left.push(items.next().unwrap().parse::<i32>().unwrap());
-            right.push(items.next().unwrap().parse::<i32>().unwrap());
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut items = line.split_whitespace();
+            let (Some(l), Some(r), None) = (items.next(), items.next(), items.next()) else {
+                bail!(
+                    "Line {}: expected two location IDs, got [{}]",
+                    index + 1,
+                    line
+                );
+            };
+
+            left.push(
+                l.parse()
+                    .with_context(|| format!("Line {}: invalid location ID [{}]", index + 1, l))?,
+            );
+            right.push(
+                r.parse()
+                    .with_context(|| format!("Line {}: invalid location ID [{}]", index + 1, r))?,
+            );
         }
 
-        let result: i32 = left
+        Ok(LocationLists { left, right })
+    }
+
+    // Pairs up the smallest of each list, then the second smallest, and so on.
+    fn distance(&self, metric: Metric) -> Result<i64> {
+        let mut left = self.left.clone();
+        let mut right = self.right.clone();
+        left.sort_unstable();
+        right.sort_unstable();
+
+        std::iter::zip(left, right)
+            .try_fold(0i64, |total, (l, r)| {
+                total.checked_add(metric.distance(l, r)?)
+            })
+            .context("Total distance is too large")
+    }
+
+    fn similarity(&self) -> Result<i64> {
+        let mut counts: HashMap<i64, i64> = HashMap::new();
+        for r in self.right.iter() {
+            *counts.entry(*r).or_default() += 1;
+        }
+
+        self.left
             .iter()
-            .map(|l| {
-                l * right
-                    .iter()
-                    .map(|r| if r == l { 1 } else { 0 })
-                    .sum::<i32>()
+            .try_fold(0i64, |total, l| {
+                total.checked_add(l.checked_mul(counts.get(l).copied().unwrap_or(0))?)
             })
-            .sum();
-        Ok(result as usize)
+            .context("Similarity score is too large")
     }
 
-    assert_eq!(31, part2(BufReader::new(TEST.as_bytes()))?);
+    // Scans the right list for every left ID. Quadratic, kept as a reference.
+    fn similarity_scanning(&self) -> Result<i64> {
+        self.left
+            .iter()
+            .try_fold(0i64, |total, l| {
+                total.checked_add(
+                    l.checked_mul(self.right.iter().filter(|r| *r == l).count() as i64)?,
+                )
+            })
+            .context("Similarity score is too large")
+    }
+}
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
-    println!("Result = {}", result);
-    //endregion
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // IDs from a small range, so that the lists share many of them.
+    fn lists() -> impl Strategy<Value = LocationLists> {
+        (0..30usize).prop_flat_map(|length| {
+            (
+                prop::collection::vec(0..20i64, length),
+                prop::collection::vec(0..20i64, length),
+            )
+                .prop_map(|(left, right)| LocationLists { left, right })
+        })
+    }
 
-    Ok(())
+    proptest! {
+        #[test]
+        fn similarity_matches_scanning(lists in lists()) {
+            prop_assert_eq!(lists.similarity_scanning().ok(), lists.similarity().ok());
+        }
+    }
 }
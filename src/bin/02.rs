@@ -1,9 +1,9 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "02";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -17,111 +17,268 @@ const TEST: &str = "\
 1 3 6 7 9
 ";
 
-fn main() -> Result<()> {
-    start_day(DAY);
+fn part1(reports: &[Vec<i32>]) -> Result<usize> {
+    let rules = SafetyRules::default();
+    let result = reports
+        .iter()
+        .filter(|levels| rules.is_safe(levels))
+        .count();
 
-    //region Part 1
-    println!("=== Part 1 ===");
+    Ok(result)
+}
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result = 0;
-        for line in reader.lines() {
-            let numbers: Vec<i32> = line?
-                .split_whitespace()
-                .map(|str| str.parse::<i32>().unwrap())
-                .collect();
+fn part2(reports: &[Vec<i32>]) -> Result<usize> {
+    let rules = SafetyRules::default();
+    let result = reports
+        .iter()
+        .filter(|levels| rules.is_safe(levels) || !rules.fixing_removals(levels).is_empty())
+        .count();
 
-            let valid = is_valid(numbers);
-            if valid {
-                result += 1;
-            }
-        }
+    Ok(result)
+}
 
-        Ok(result)
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |reports| part1(reports))?;
+        bench(DAY, 2, parse, |reports| part2(reports))?;
+        return Ok(());
     }
 
+    //region Part 1
+    println!("=== Part 1 ===");
+
     // Set the expected answer for the test input
-    assert_eq!(2, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(2, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result = 0;
-        for line in reader.lines() {
-            let numbers: Vec<i32> = line?
-                .split_whitespace()
-                .map(|str| str.parse::<i32>().unwrap())
-                .collect();
+    assert_eq!(4, part2(&parse(TEST)?)?);
 
-            if is_valid(numbers.clone()) {
-                result += 1;
-                continue;
-            }
-
-            for i in 0..numbers.len() {
-                let mut attempt = numbers.clone();
-                attempt.remove(i);
-                if is_valid(attempt) {
-                    result += 1;
-                    break;
-                }
-            }
-        }
-
-        Ok(result)
+    let rules = SafetyRules::default();
+    let reports = parse(TEST)?;
+    assert_eq!(
+        vec![
+            vec![0, 1, 3, 4],
+            vec![],
+            vec![],
+            vec![1, 2],
+            vec![2, 3],
+            vec![0, 3, 4]
+        ],
+        reports
+            .iter()
+            .map(|levels| rules.fixing_removals(levels))
+            .collect::<Vec<_>>()
+    );
+    for levels in reports.iter() {
+        assert_eq!(
+            rules.is_safe_removing_each(levels),
+            rules.is_safe_with_removals(levels, 1)
+        );
     }
+    assert_eq!(
+        vec![true, false, false, true, true, true],
+        reports
+            .iter()
+            .map(|levels| rules.is_safe_with_removals(levels, 1))
+            .collect::<Vec<_>>()
+    );
+    assert!(reports
+        .iter()
+        .all(|levels| rules.is_safe_with_removals(levels, 2)));
+    assert!(SafetyRules::new(1, 5).is_safe(&[1, 2, 7, 8, 9]));
+    assert!(!SafetyRules::new(2, 3).is_safe(&[1, 3, 6, 7, 9]));
 
-    assert_eq!(4, part2(BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
     Ok(())
 }
 
-fn is_valid(numbers: Vec<i32>) -> bool {
-    let mut num_iter = numbers.iter();
-    let mut last = num_iter.next().unwrap();
-
-    let mut valid = true;
-    let mut direction = 0;
-    for next in num_iter {
-        let diff = next - last;
-        if direction == 0 {
-            if diff > 0 {
-                direction = 1;
-            } else {
-                direction = -1;
+fn parse(input: &str) -> Result<Vec<Vec<i32>>> {
+    input.lines().map(parse_report).collect()
+}
+
+fn parse_report(line: &str) -> Result<Vec<i32>> {
+    line.split_whitespace()
+        .map(|str| {
+            str.parse::<i32>()
+                .with_context(|| format!("Invalid level [{}]", str))
+        })
+        .collect()
+}
+
+// A report is safe when its levels all increase or all decrease, and every pair of adjacent
+// levels differs by at least `min_step` and at most `max_step`.
+#[derive(Debug, Clone, Copy)]
+struct SafetyRules {
+    min_step: i32,
+    max_step: i32,
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        SafetyRules::new(1, 3)
+    }
+}
+
+impl SafetyRules {
+    fn new(min_step: i32, max_step: i32) -> SafetyRules {
+        SafetyRules { min_step, max_step }
+    }
+
+    fn step_ok(&self, from: i32, to: i32, increasing: bool) -> bool {
+        let diff = if increasing { to - from } else { from - to };
+        (self.min_step..=self.max_step).contains(&diff)
+    }
+
+    fn is_safe(&self, levels: &[i32]) -> bool {
+        [true, false].into_iter().any(|increasing| {
+            levels
+                .windows(2)
+                .all(|pair| self.step_ok(pair[0], pair[1], increasing))
+        })
+    }
+
+    // Tries every removal on a copy of the report. Quadratic, kept as a reference.
+    fn is_safe_removing_each(&self, levels: &[i32]) -> bool {
+        if self.is_safe(levels) {
+            return true;
+        }
+
+        (0..levels.len()).any(|i| {
+            let mut attempt = levels.to_vec();
+            attempt.remove(i);
+            self.is_safe(&attempt)
+        })
+    }
+
+    // Indices of the levels that make the report safe when removed, in linear time. Removing
+    // level i works when the levels before it are safe, the levels after it are safe, and its two
+    // neighbours are a valid step from one another.
+    fn fixing_removals(&self, levels: &[i32]) -> Vec<usize> {
+        let n = levels.len();
+        let mut fixing = vec![false; n];
+
+        for increasing in [true, false] {
+            let ok = |from: usize, to: usize| self.step_ok(levels[from], levels[to], increasing);
+
+            let mut prefix_safe = vec![true; n];
+            for i in 1..n {
+                prefix_safe[i] = prefix_safe[i - 1] && ok(i - 1, i);
+            }
+            let mut suffix_safe = vec![true; n];
+            for i in (0..n.saturating_sub(1)).rev() {
+                suffix_safe[i] = suffix_safe[i + 1] && ok(i, i + 1);
             }
-            if direction == 0 {
-                valid = false;
-                break;
+
+            for i in 0..n {
+                let before = i == 0 || prefix_safe[i - 1];
+                let after = i + 1 == n || suffix_safe[i + 1];
+                let bridge = i == 0 || i + 1 == n || ok(i - 1, i + 1);
+                fixing[i] |= before && after && bridge;
             }
         }
 
-        let abs_diff = diff.abs();
-        if !(1..=3).contains(&abs_diff) {
-            valid = false;
-            break;
+        (0..n).filter(|i| fixing[*i]).collect()
+    }
+
+    // Whether removing at most `k` levels makes the report safe. For each level, finds the fewest
+    // removals that leave a safe report ending on it, looking back at most k + 1 levels.
+    fn is_safe_with_removals(&self, levels: &[i32], k: usize) -> bool {
+        let n = levels.len();
+        if n <= k + 1 {
+            return true;
+        }
+
+        [true, false].into_iter().any(|increasing| {
+            let mut removals = vec![usize::MAX; n];
+            for i in 0..n {
+                removals[i] = i;
+                for j in i.saturating_sub(k + 1)..i {
+                    if removals[j] != usize::MAX && self.step_ok(levels[j], levels[i], increasing) {
+                        removals[i] = removals[i].min(removals[j] + i - j - 1);
+                    }
+                }
+            }
+
+            (0..n).any(|i| removals[i] + (n - 1 - i) <= k)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    // Short reports with small steps, so that safe ones are common.
+    fn report() -> impl Strategy<Value = Vec<i32>> {
+        (0..20i32, prop::collection::vec(-4..=4i32, 0..8)).prop_map(|(first, steps)| {
+            std::iter::once(first)
+                .chain(steps.into_iter().scan(first, |level, step| {
+                    *level += step;
+                    Some(*level)
+                }))
+                .collect()
+        })
+    }
+
+    fn rules() -> impl Strategy<Value = SafetyRules> {
+        (0..3i32, 0..4i32)
+            .prop_map(|(min_step, spread)| SafetyRules::new(min_step, min_step + spread))
+    }
+
+    fn without(levels: &[i32], removed: &[usize]) -> Vec<i32> {
+        levels
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !removed.contains(i))
+            .map(|(_, level)| *level)
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn fixing_removals_match_removing_each(levels in report(), rules in rules()) {
+            let expected: Vec<usize> = (0..levels.len())
+                .filter(|i| rules.is_safe(&without(&levels, &[*i])))
+                .collect();
+            prop_assert_eq!(expected, rules.fixing_removals(&levels));
         }
 
-        if direction > 0 && diff < 0 {
-            valid = false;
-            break;
+        #[test]
+        fn one_removal_matches_removing_each(levels in report(), rules in rules()) {
+            prop_assert_eq!(
+                rules.is_safe_removing_each(&levels),
+                rules.is_safe_with_removals(&levels, 1)
+            );
         }
-        if direction < 0 && diff > 0 {
-            valid = false;
-            break;
+
+        #[test]
+        fn removals_match_removing_every_combination(
+            levels in report(),
+            rules in rules(),
+            k in 0..4usize,
+        ) {
+            let expected = (0..=k).any(|count| {
+                (0..levels.len())
+                    .combinations(count)
+                    .any(|removed| rules.is_safe(&without(&levels, &removed)))
+            });
+            prop_assert_eq!(expected, rules.is_safe_with_removals(&levels, k));
         }
-        last = next;
     }
-    valid
 }
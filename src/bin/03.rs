@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
 use anyhow::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use adv_code_2024::*;
-use regex::Regex;
+use std::fs;
+use std::io::{BufRead, BufReader};
 
 const DAY: &str = "03";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -15,69 +15,352 @@ xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))
 const TEST_2: &str = "\
 xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))
 ";
+const TEST_EXTENSIONS: &str = "\
+mul(2,3)add(4,5)don't()add(1,1)do()neg(7)add(1,2,3)add(10,20)
+";
+
+// Instructions beyond the puzzle's own, written as `name(a,b,...)` with exactly `arity` numbers.
+#[derive(Debug, Clone, Copy)]
+struct Extension {
+    name: &'static str,
+    arity: usize,
+    eval: fn(&[usize]) -> usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Instruction {
+    Mul(usize, usize),
+    Do,
+    Dont,
+    // Index into the extensions and the arguments.
+    Extension(usize, Vec<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    offset: usize,
+    instruction: Instruction,
+}
+
+enum Scan<T> {
+    // What was found and how many bytes it takes.
+    Found(T, usize),
+    // The input ended before telling whether there is an instruction here.
+    Incomplete,
+    NoMatch,
+}
+
+// Finds instructions in the corrupted memory as it is fed, in chunks of any size. Bytes that may
+// still be the start of an instruction are kept until the next chunk tells.
+struct Tokenizer<'a> {
+    extensions: &'a [Extension],
+    pending: Vec<u8>,
+    // Offset of the first pending byte in the whole input.
+    offset: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(extensions: &'a [Extension]) -> Tokenizer<'a> {
+        Tokenizer {
+            extensions,
+            pending: vec![],
+            offset: 0,
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Vec<Token> {
+        self.pending.extend_from_slice(chunk);
+        self.scan(false)
+    }
+
+    fn finish(&mut self) -> Vec<Token> {
+        self.scan(true)
+    }
+
+    fn scan(&mut self, last: bool) -> Vec<Token> {
+        let mut tokens = vec![];
+        let mut pos = 0;
+
+        while pos < self.pending.len() {
+            match self.scan_at(&self.pending[pos..]) {
+                Scan::Found(instruction, length) => {
+                    tokens.push(Token {
+                        offset: self.offset + pos,
+                        instruction,
+                    });
+                    pos += length;
+                }
+                Scan::Incomplete if !last => break,
+                Scan::Incomplete | Scan::NoMatch => pos += 1,
+            }
+        }
+
+        self.pending.drain(..pos);
+        self.offset += pos;
+
+        tokens
+    }
+
+    fn scan_at(&self, input: &[u8]) -> Scan<Instruction> {
+        let mut incomplete = false;
+
+        let builtins = [("mul", 2), ("do", 0), ("don't", 0)];
+        let extensions = self.extensions.iter().map(|e| (e.name, e.arity));
+
+        for (index, (name, arity)) in builtins.into_iter().chain(extensions).enumerate() {
+            match scan_call(input, name, arity) {
+                Scan::Found(args, length) => {
+                    let instruction = match index {
+                        0 => Instruction::Mul(args[0], args[1]),
+                        1 => Instruction::Do,
+                        2 => Instruction::Dont,
+                        _ => Instruction::Extension(index - builtins.len(), args),
+                    };
+                    return Scan::Found(instruction, length);
+                }
+                Scan::Incomplete => incomplete = true,
+                Scan::NoMatch => (),
+            }
+        }
+
+        if incomplete {
+            Scan::Incomplete
+        } else {
+            Scan::NoMatch
+        }
+    }
+}
+
+// Matches `name(` followed by `arity` comma separated numbers of 1 to 3 digits and `)`.
+fn scan_call(input: &[u8], name: &str, arity: usize) -> Scan<Vec<usize>> {
+    let mut expected = name.as_bytes().to_vec();
+    expected.push(b'(');
+
+    let prefix = expected.len().min(input.len());
+    if input[..prefix] != expected[..prefix] {
+        return Scan::NoMatch;
+    }
+    if input.len() < expected.len() {
+        return Scan::Incomplete;
+    }
+
+    let mut pos = expected.len();
+    let mut args = vec![];
+    for i in 0..arity {
+        let digits = input[pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if pos + digits == input.len() && digits < 3 {
+            return Scan::Incomplete;
+        }
+        if !(1..=3).contains(&digits) {
+            return Scan::NoMatch;
+        }
+
+        let number = std::str::from_utf8(&input[pos..pos + digits]).unwrap();
+        args.push(number.parse().unwrap());
+        pos += digits;
+
+        if i + 1 < arity {
+            match input.get(pos) {
+                None => return Scan::Incomplete,
+                Some(b',') => pos += 1,
+                Some(_) => return Scan::NoMatch,
+            }
+        }
+    }
+
+    match input.get(pos) {
+        None => Scan::Incomplete,
+        Some(b')') => Scan::Found(args, pos + 1),
+        Some(_) => Scan::NoMatch,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    token: Token,
+    // Whether instructions were enabled when this one ran.
+    enabled: bool,
+    value: usize,
+}
+
+// Adds up the value of every instruction. When `conditionals` is set, `do()` and `don't()`
+// switch the following instructions on and off.
+struct Interpreter<'a> {
+    extensions: &'a [Extension],
+    conditionals: bool,
+    enabled: bool,
+    result: usize,
+    trace: Vec<Step>,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(extensions: &'a [Extension], conditionals: bool) -> Interpreter<'a> {
+        Interpreter {
+            extensions,
+            conditionals,
+            enabled: true,
+            result: 0,
+            trace: vec![],
+        }
+    }
+
+    fn execute(&mut self, token: Token) {
+        let enabled = self.enabled || !self.conditionals;
+
+        let value = match &token.instruction {
+            Instruction::Do => {
+                self.enabled = true;
+                0
+            }
+            Instruction::Dont => {
+                self.enabled = false;
+                0
+            }
+            _ if !enabled => 0,
+            Instruction::Mul(a, b) => a * b,
+            Instruction::Extension(index, args) => (self.extensions[*index].eval)(args),
+        };
+
+        self.result += value;
+        self.trace.push(Step {
+            token,
+            enabled,
+            value,
+        });
+    }
+}
+
+fn run<R: BufRead>(
+    mut reader: R,
+    extensions: &[Extension],
+    conditionals: bool,
+) -> Result<Interpreter<'_>> {
+    let mut tokenizer = Tokenizer::new(extensions);
+    let mut interpreter = Interpreter::new(extensions, conditionals);
+
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+
+        let length = chunk.len();
+        tokenizer
+            .feed(chunk)
+            .into_iter()
+            .for_each(|token| interpreter.execute(token));
+        reader.consume(length);
+    }
+    tokenizer
+        .finish()
+        .into_iter()
+        .for_each(|token| interpreter.execute(token));
+
+    Ok(interpreter)
+}
+
+// The puzzle's own instructions, found in the whole input at once.
+fn parse(input: &str) -> Result<Vec<Token>> {
+    let mut tokenizer = Tokenizer::new(&[]);
+    let mut tokens = tokenizer.feed(input.as_bytes());
+    tokens.extend(tokenizer.finish());
+
+    Ok(tokens)
+}
+
+fn interpret(tokens: &[Token], conditionals: bool) -> usize {
+    let mut interpreter = Interpreter::new(&[], conditionals);
+    tokens
+        .iter()
+        .cloned()
+        .for_each(|token| interpreter.execute(token));
+
+    interpreter.result
+}
+
+fn part1(tokens: &[Token]) -> Result<usize> {
+    Ok(interpret(tokens, false))
+}
+
+fn part2(tokens: &[Token]) -> Result<usize> {
+    Ok(interpret(tokens, true))
+}
 
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |tokens| part1(tokens))?;
+        bench(DAY, 2, parse, |tokens| part2(tokens))?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result : usize = 0;
-        let re = Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)")?;
-        for line in reader.lines() {
-            result += re.captures_iter(&line.unwrap())
-                .map(|cap| {
-                    let a = cap.get(1).unwrap().as_str().parse::<usize>().unwrap();
-                    let b = cap.get(2).unwrap().as_str().parse::<usize>().unwrap();
-
-                    a * b
-                }).sum::<usize>();
-        }
-        Ok(result)
-    }
-
-    assert_eq!(161, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(161, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
-    
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result : usize = 0;
-        let mut calc = true;
-        let re = Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)|don't\(\)|do\(\)")?;
-        for line in reader.lines() {
-            result += re.captures_iter(&line.unwrap())
-                .map(|cap| {
-                    let mut a  = 0;
-                    let mut b = 0;
-                    match cap.get(0).unwrap().as_str() {
-                        "do()" => calc = true,
-                        "don't()" => calc = false,
-                        _ => {
-                            a = cap.get(1).unwrap().as_str().parse::<usize>().unwrap();
-                            b = cap.get(2).unwrap().as_str().parse::<usize>().unwrap();
-                        }
-                    };
-                    if calc {
-                        a * b
-                    } else {
-                        0
-                    }
-                }).sum::<usize>();
-        }
-        Ok(result)
-    }
-    
-    assert_eq!(48, part2(BufReader::new(TEST_2.as_bytes()))?);
-    
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+
+    assert_eq!(48, part2(&parse(TEST_2)?)?);
+
+    let interpreter = run(BufReader::new(TEST_2.as_bytes()), &[], true)?;
+    assert!(interpreter.enabled);
+    assert_eq!(
+        vec![
+            (1, Instruction::Mul(2, 4), true),
+            (20, Instruction::Dont, true),
+            (28, Instruction::Mul(5, 5), false),
+            (48, Instruction::Mul(11, 8), false),
+            (59, Instruction::Do, false),
+            (64, Instruction::Mul(8, 5), true),
+        ],
+        interpreter
+            .trace
+            .iter()
+            .map(|s| (s.token.offset, s.token.instruction.clone(), s.enabled))
+            .collect::<Vec<_>>()
+    );
+
+    // Same trace when the input arrives one byte at a time.
+    let single_bytes = BufReader::with_capacity(1, TEST_2.as_bytes());
+    assert_eq!(interpreter.trace, run(single_bytes, &[], true)?.trace);
+
+    let extensions = [
+        Extension {
+            name: "add",
+            arity: 2,
+            eval: |args| args[0] + args[1],
+        },
+        Extension {
+            name: "neg",
+            arity: 1,
+            eval: |_| 0,
+        },
+    ];
+    let interpreter = run(
+        BufReader::with_capacity(3, TEST_EXTENSIONS.as_bytes()),
+        &extensions,
+        true,
+    )?;
+    assert_eq!(6 + 9 + 30, interpreter.result);
+    assert_eq!(
+        Instruction::Extension(1, vec![7]),
+        interpreter.trace[5].token.instruction
+    );
+
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
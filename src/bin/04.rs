@@ -1,9 +1,9 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "04";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -21,203 +21,327 @@ MAMMMXMMMM
 MXMXAXMASX
 ";
 
-struct CharMatrix {
-    matrix: Vec<String>,
+// The puzzle grid, one byte per letter.
+struct Grid {
+    cells: Vec<u8>,
     row_count: i32,
     col_count: i32,
 }
 
-impl CharMatrix {
-    fn new(matrix: Vec<String>) -> CharMatrix {
-        let row_count: usize = matrix.len();
-        let col_count = matrix
-            .first()
-            .expect("There is a firt line in the problem")
-            .len();
-        CharMatrix {
-            matrix,
-            row_count: row_count.try_into().unwrap(),
-            col_count: col_count.try_into().unwrap(),
-        }
-    }
+// Direction a word is read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Orientation {
+    East,
+    South,
+    SouthEast,
+    SouthWest,
+    West,
+    North,
+    NorthWest,
+    NorthEast,
+}
 
-    fn row_count(&self) -> i32 {
-        self.row_count
-    }
+// Clockwise rotation applied to a pattern template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rotation {
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
 
-    fn col_count(&self) -> i32 {
-        self.col_count
+#[derive(Debug, PartialEq, Eq)]
+struct WordMatch<'a> {
+    word: &'a str,
+    row: i32,
+    col: i32,
+    orientation: Orientation,
+}
+
+// A 2D template where `.` matches any letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+    rows: Vec<Vec<u8>>,
+}
+
+// Position of the top left corner of the rotated template.
+#[derive(Debug, PartialEq, Eq)]
+struct PatternMatch {
+    row: i32,
+    col: i32,
+    rotation: Rotation,
+}
+
+const WILDCARD: u8 = b'.';
+
+impl Orientation {
+    // Left to right and top to bottom, the way words are normally read.
+    const FORWARD: [Orientation; 4] = [
+        Orientation::East,
+        Orientation::South,
+        Orientation::SouthEast,
+        Orientation::SouthWest,
+    ];
+
+    const ALL: [Orientation; 8] = [
+        Orientation::East,
+        Orientation::South,
+        Orientation::SouthEast,
+        Orientation::SouthWest,
+        Orientation::West,
+        Orientation::North,
+        Orientation::NorthWest,
+        Orientation::NorthEast,
+    ];
+
+    fn step(&self) -> (i32, i32) {
+        match self {
+            Orientation::East => (0, 1),
+            Orientation::South => (1, 0),
+            Orientation::SouthEast => (1, 1),
+            Orientation::SouthWest => (1, -1),
+            Orientation::West => (0, -1),
+            Orientation::North => (-1, 0),
+            Orientation::NorthWest => (-1, -1),
+            Orientation::NorthEast => (-1, 1),
+        }
     }
+}
 
-    fn char_at(&self, row: i32, col: i32) -> &str {
-        self.matrix
-            .get(row as usize)
-            .expect("row exists")
-            .get(col as usize..(col + 1) as usize)
-            .unwrap()
+impl Pattern {
+    fn new(rows: &[&str]) -> Pattern {
+        Pattern {
+            rows: rows.iter().map(|row| row.as_bytes().to_vec()).collect(),
+        }
     }
 
-    fn find_string(
-        &self,
-        start_row: i32,
-        start_col: i32,
-        row_step: i32,
-        col_step: i32,
-        needle: &str,
-    ) -> bool {
-        let mut row = start_row;
-        let mut col = start_col;
-        let needle_part = needle.chars();
-
-        for char in needle_part {
-            if row < 0 || row >= self.row_count() {
-                return false;
-            }
-            if col < 0 || col >= self.col_count() {
-                return false;
-            }
+    fn rotate_clockwise(&self) -> Pattern {
+        let row_count = self.rows.len();
+        let col_count = self.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        Pattern {
+            rows: (0..col_count)
+                .map(|col| {
+                    (0..row_count)
+                        .rev()
+                        .map(|row| *self.rows[row].get(col).unwrap_or(&WILDCARD))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
 
-            if Some(char.to_string().as_str())
-                != self
-                    .matrix
-                    .get(row as usize)
-                    .and_then(|row| row.get(col as usize..col as usize + 1))
-            {
-                return false;
+    // Every distinct rotation, so symmetric patterns are not matched twice at the same place.
+    fn rotations(&self) -> Vec<(Rotation, Pattern)> {
+        let mut rotations: Vec<(Rotation, Pattern)> = vec![];
+        let mut pattern = self.clone();
+
+        for rotation in [
+            Rotation::None,
+            Rotation::Quarter,
+            Rotation::Half,
+            Rotation::ThreeQuarters,
+        ] {
+            if rotations.iter().all(|(_, p)| *p != pattern) {
+                rotations.push((rotation, pattern.clone()));
             }
-
-            row += row_step;
-            col += col_step;
+            pattern = pattern.rotate_clockwise();
         }
 
-        true
+        rotations
     }
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+impl Grid {
+    fn new(lines: Vec<String>) -> Grid {
+        let row_count = lines.len();
+        let col_count = lines
+            .first()
+            .expect("There is a firt line in the problem")
+            .len();
 
-    //region Part 1
-    println!("=== Part 1 ===");
+        Grid {
+            cells: lines.iter().flat_map(|line| line.bytes()).collect(),
+            row_count: row_count.try_into().unwrap(),
+            col_count: col_count.try_into().unwrap(),
+        }
+    }
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let input: Vec<String> = reader.lines().map(|r| r.expect("Line was read.")).collect();
+    fn byte_at(&self, row: i32, col: i32) -> Option<u8> {
+        if row < 0 || row >= self.row_count || col < 0 || col >= self.col_count {
+            return None;
+        }
 
-        let matrix = CharMatrix::new(input);
-        let row_count = matrix.row_count();
-        let col_count = matrix.col_count();
-        let mut result = 0;
+        Some(self.cells[(row * self.col_count + col) as usize])
+    }
 
-        for row in 0..row_count {
-            for col in 0..col_count {
-                if matrix.char_at(row, col) != "X" {
-                    continue;
-                }
+    fn find_string(&self, row: i32, col: i32, orientation: Orientation, needle: &[u8]) -> bool {
+        let (row_step, col_step) = orientation.step();
 
-                // horizontals
-                if matrix.find_string(row, col, 0, 1, "XMAS") {
-                    result += 1;
-                }
-                if matrix.find_string(row, col, 0, -1, "XMAS") {
-                    result += 1;
-                }
+        needle.iter().enumerate().all(|(i, expected)| {
+            let i = i as i32;
+            self.byte_at(row + i * row_step, col + i * col_step) == Some(*expected)
+        })
+    }
 
-                // verticals
-                if matrix.find_string(row, col, 1, 0, "XMAS") {
-                    result += 1;
-                }
-                if matrix.find_string(row, col, -1, 0, "XMAS") {
-                    result += 1;
+    // Every occurrence of every word. Without reversal words are only read forward, left to
+    // right and top to bottom; with it they are also read backwards.
+    fn find_words<'a>(&self, words: &[&'a str], reversal: bool) -> Vec<WordMatch<'a>> {
+        let orientations: &[Orientation] = if reversal {
+            &Orientation::ALL
+        } else {
+            &Orientation::FORWARD
+        };
+
+        let mut matches = vec![];
+        for row in 0..self.row_count {
+            for col in 0..self.col_count {
+                for word in words {
+                    if word.as_bytes().first() != self.byte_at(row, col).as_ref() {
+                        continue;
+                    }
+
+                    for orientation in orientations {
+                        if self.find_string(row, col, *orientation, word.as_bytes()) {
+                            matches.push(WordMatch {
+                                word,
+                                row,
+                                col,
+                                orientation: *orientation,
+                            });
+                        }
+                    }
                 }
+            }
+        }
 
-                // diagonals
-                if matrix.find_string(row, col, 1, 1, "XMAS") {
-                    result += 1;
-                }
-                if matrix.find_string(row, col, 1, -1, "XMAS") {
-                    result += 1;
-                }
-                if matrix.find_string(row, col, -1, 1, "XMAS") {
-                    result += 1;
-                }
-                if matrix.find_string(row, col, -1, -1, "XMAS") {
-                    result += 1;
+        matches
+    }
+
+    fn find_pattern(&self, pattern: &Pattern) -> Vec<PatternMatch> {
+        let mut matches = vec![];
+
+        for (rotation, rotated) in pattern.rotations() {
+            for row in 0..self.row_count {
+                for col in 0..self.col_count {
+                    if self.matches_at(row, col, &rotated) {
+                        matches.push(PatternMatch { row, col, rotation });
+                    }
                 }
             }
         }
 
-        Ok(result)
+        matches
     }
 
-    assert_eq!(18, part1(BufReader::new(TEST.as_bytes()))?);
+    fn matches_at(&self, row: i32, col: i32, pattern: &Pattern) -> bool {
+        pattern.rows.iter().enumerate().all(|(r, pattern_row)| {
+            pattern_row.iter().enumerate().all(|(c, expected)| {
+                *expected == WILDCARD
+                    || self.byte_at(row + r as i32, col + c as i32) == Some(*expected)
+            })
+        })
+    }
+}
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
-    println!("Result = {}", result);
-    //endregion
+fn parse(input: &str) -> Result<Grid> {
+    let lines: Vec<String> = input.lines().map(String::from).collect();
+    ensure!(!lines.is_empty(), "The grid is empty");
+    ensure!(
+        lines.iter().all(|line| line.len() == lines[0].len()),
+        "Grid rows have different lengths"
+    );
 
-    //region Part 2
-    println!("\n=== Part 2 ===");
+    Ok(Grid::new(lines))
+}
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let input: Vec<String> = reader.lines().map(|r| r.expect("Line was read.")).collect();
+fn part1(grid: &Grid) -> Result<usize> {
+    Ok(grid.find_words(&["XMAS"], true).len())
+}
 
-        let matrix = CharMatrix::new(input);
-        let row_count = matrix.row_count();
-        let col_count = matrix.col_count();
-        let mut result = 0;
+fn part2(grid: &Grid) -> Result<usize> {
+    // M.S
+    // .A.
+    // M.S
+    let x_mas = Pattern::new(&["M.S", ".A.", "M.S"]);
 
-        for row in 0..row_count {
-            for col in 0..col_count {
-                if matrix.char_at(row, col) != "A" {
-                    continue;
-                }
+    Ok(grid.find_pattern(&x_mas).len())
+}
 
-                // M.M
-                // .A.
-                // S.S
-                if matrix.find_string(row - 1, col - 1, 1, 1, "MAS")
-                    && matrix.find_string(row - 1, col + 1, 1, -1, "MAS")
-                {
-                    result += 1;
-                }
+fn main() -> Result<()> {
+    start_day(DAY);
 
-                // M.S
-                // .A.
-                // M.S
-                if matrix.find_string(row - 1, col - 1, 1, 1, "MAS")
-                    && matrix.find_string(row + 1, col - 1, -1, 1, "MAS")
-                {
-                    result += 1;
-                }
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
 
-                // S.M
-                // .A.
-                // S.M
-                if matrix.find_string(row - 1, col + 1, 1, -1, "MAS")
-                    && matrix.find_string(row + 1, col + 1, -1, -1, "MAS")
-                {
-                    result += 1;
-                }
+    //region Part 1
+    println!("=== Part 1 ===");
 
-                // S.S
-                // .A.
-                // M.M
-                if matrix.find_string(row + 1, col - 1, -1, 1, "MAS")
-                    && matrix.find_string(row + 1, col + 1, -1, -1, "MAS")
-                {
-                    result += 1;
-                }
-            }
-        }
+    assert_eq!(18, part1(&parse(TEST)?)?);
+
+    let grid = parse(TEST)?;
+    assert_eq!(18, grid.find_words(&["XMAS", "SAMX"], false).len());
+    let forward = grid.find_words(&["XMAS"], false);
+    assert_eq!(
+        WordMatch {
+            word: "XMAS",
+            row: 0,
+            col: 4,
+            orientation: Orientation::SouthEast
+        },
+        forward[0]
+    );
+    assert_eq!(
+        WordMatch {
+            word: "XMAS",
+            row: 0,
+            col: 5,
+            orientation: Orientation::East
+        },
+        forward[1]
+    );
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
 
-        Ok(result)
-    }
+    //region Part 2
+    println!("\n=== Part 2 ===");
 
-    assert_eq!(9, part2(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(9, part2(&parse(TEST)?)?);
+
+    let plus = Pattern::new(&[".M.", "MAS", ".S."]);
+    assert_eq!(4, plus.rotations().len());
+    assert_eq!(1, Pattern::new(&["M.M", ".A.", "M.M"]).rotations().len());
+    let grid = Grid::new(vec![
+        ".M..".into(),
+        "MAS.".into(),
+        ".SAM".into(),
+        "..M.".into(),
+    ]);
+    assert_eq!(
+        vec![
+            PatternMatch {
+                row: 0,
+                col: 0,
+                rotation: Rotation::None
+            },
+            PatternMatch {
+                row: 1,
+                col: 1,
+                rotation: Rotation::Half
+            }
+        ],
+        grid.find_pattern(&plus)
+    );
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::convert::From;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
+use std::str::FromStr;
 
 const DAY: &str = "05";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -51,14 +51,15 @@ impl Rule {
     }
 }
 
-impl From<String> for Rule {
-    fn from(rule: String) -> Self {
-        let parts: Vec<&str> = rule.split("|").collect();
+impl FromStr for Rule {
+    type Err = Error;
 
-        Rule::new(
-            parts.first().unwrap().to_string(),
-            parts.get(1).unwrap().to_string(),
-        )
+    fn from_str(rule: &str) -> Result<Rule> {
+        let (page, before) = rule
+            .split_once('|')
+            .with_context(|| format!("Invalid rule [{}]", rule))?;
+
+        Ok(Rule::new(page.to_string(), before.to_string()))
     }
 }
 
@@ -92,7 +93,37 @@ impl RuleList {
         true
     }
 
-    fn fix_update(&self, mut update: Vec<String>) -> Vec<String> {
+    // Whether the rules between pages of the update can all be met at once, by taking out pages
+    // that no remaining rule wants after another one.
+    fn is_consistent(&self, update: &[String]) -> bool {
+        let mut rules: Vec<&Rule> = self
+            .list
+            .iter()
+            .filter(|r| update.contains(&r.page) && update.contains(&r.before))
+            .collect();
+        let mut pages: Vec<&String> = update.iter().collect();
+
+        while !pages.is_empty() {
+            let Some(first) = pages
+                .iter()
+                .position(|p| rules.iter().all(|r| r.before != **p))
+            else {
+                return false;
+            };
+            let page = pages.remove(first);
+            rules.retain(|r| r.page != *page);
+        }
+
+        true
+    }
+
+    fn fix_update(&self, mut update: Vec<String>) -> Result<Vec<String>> {
+        ensure!(
+            self.is_consistent(&update),
+            "Rules contradict each other for update {:?}",
+            update
+        );
+
         let mut run_fixes = true;
         while run_fixes {
             run_fixes = false;
@@ -113,87 +144,90 @@ impl RuleList {
             }
         }
 
-        update
+        Ok(update)
     }
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+struct Manual {
+    rules: RuleList,
+    updates: Vec<Vec<String>>,
+}
 
-    //region Part 1
-    println!("=== Part 1 ===");
+fn parse(input: &str) -> Result<Manual> {
+    let mut rules = RuleList::new();
+    let mut updates = vec![];
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut rule_list = RuleList::new();
-        let mut load = true;
-        let mut result = 0;
-
-        for line in reader.lines() {
-            match line {
-                Result::Ok(read) if read.is_empty() => load = false,
-                Result::Ok(read) => {
-                    if load {
-                        rule_list.add(Rule::from(read));
-                    } else {
-                        let update: Vec<String> =
-                            read.split(",").map(|str| str.to_string()).collect();
-                        if rule_list.is_valid(&update) {
-                            let middle = update.len() / 2;
-                            let value = update[middle].parse::<usize>().unwrap();
-                            result += value;
-                        }
-                    }
-                }
-                Err(_) => panic!("Error reading"),
-            }
+    let mut lines = input.lines();
+    for line in lines.by_ref().take_while(|line| !line.is_empty()) {
+        rules.add(line.parse()?);
+    }
+    for line in lines {
+        updates.push(line.split(",").map(|str| str.to_string()).collect());
+    }
+
+    Ok(Manual { rules, updates })
+}
+
+fn middle_page(update: &[String]) -> Result<usize> {
+    let page = &update[update.len() / 2];
+    page.parse::<usize>()
+        .with_context(|| format!("Invalid page [{}]", page))
+}
+
+fn part1(manual: &Manual) -> Result<usize> {
+    let mut result: usize = 0;
+    for update in manual.updates.iter() {
+        if manual.rules.is_valid(update) {
+            result = result
+                .checked_add(middle_page(update)?)
+                .context("Sum of middle pages is too large")?;
         }
+    }
+
+    Ok(result)
+}
+
+fn part2(manual: &Manual) -> Result<usize> {
+    let mut result: usize = 0;
+    for update in manual.updates.iter() {
+        if !manual.rules.is_valid(update) {
+            let update = manual.rules.fix_update(update.clone())?;
+            result = result
+                .checked_add(middle_page(&update)?)
+                .context("Sum of middle pages is too large")?;
+        }
+    }
+
+    Ok(result)
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
 
-        Ok(result)
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
     }
 
-    assert_eq!(143, part1(BufReader::new(TEST.as_bytes()))?);
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(143, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut rule_list = RuleList::new();
-        let mut load = true;
-        let mut result = 0;
-
-        for line in reader.lines() {
-            match line {
-                Result::Ok(read) if read.is_empty() => load = false,
-                Result::Ok(read) => {
-                    if load {
-                        rule_list.add(Rule::from(read));
-                    } else {
-                        let mut update: Vec<String> =
-                            read.split(",").map(|str| str.to_string()).collect();
-                        if !rule_list.is_valid(&update) {
-                            update = rule_list.fix_update(update);
-                            let middle = update.len() / 2;
-                            let value = update[middle].parse::<usize>().unwrap();
-                            result += value;
-                        }
-                    }
-                }
-                Err(_) => panic!("Error reading"),
-            }
-        }
-
-        Ok(result)
-    }
-
-    assert_eq!(123, part2(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(123, part2(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
@@ -1,11 +1,14 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::terminal::{fps_from_args, print_frame, Animation, Cell, Color, Scene};
 use adv_code_2024::*;
 use anyhow::*;
 use bitflags::bitflags;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use itertools::Itertools;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
 
 const DAY: &str = "06";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -43,10 +46,32 @@ enum Tile {
     Visited { dirs: Directions },
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Guard position and the direction it is facing.
+type Guard = (i32, i32, Directions);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum SimulationResult {
     GuardExited,
-    CycleDetected,
+    CycleDetected(LoopWitness),
+}
+
+// Proof of a loop: the first cell and direction the guard went through a second time, and how
+// many steps it takes to go around the loop once.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct LoopWitness {
+    closes_at: Guard,
+    length: usize,
+}
+
+// The patrol summarized as the cells where the guard turned, each with the direction it was
+// walking when it reached it. When the guard loops, `loop_start` is the first turn of the loop.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct PatrolTrace {
+    start: Guard,
+    obstacle: Option<(i32, i32)>,
+    turns: Vec<Guard>,
+    loop_start: Option<usize>,
+    result: SimulationResult,
 }
 
 #[derive(Debug)]
@@ -137,7 +162,11 @@ impl Game {
         match self.tile_at(self.x, self.y) {
             Tile::Visited { dirs } => {
                 if dirs.intersects(self.d) {
-                    return Some(SimulationResult::CycleDetected);
+                    let closes_at = (self.x, self.y, self.d);
+                    return Some(SimulationResult::CycleDetected(LoopWitness {
+                        closes_at,
+                        length: self.loop_length(closes_at),
+                    }));
                 }
                 self.set_tile_at(
                     self.x,
@@ -156,7 +185,17 @@ impl Game {
             }
         }
 
+        let mut turns = 0;
         while self.next_is_obstacle() {
+            // Boxed in on all four sides, the guard turns in place forever.
+            if turns == 4 {
+                return Some(SimulationResult::CycleDetected(LoopWitness {
+                    closes_at: (self.x, self.y, self.d),
+                    length: 0,
+                }));
+            }
+            turns += 1;
+
             match self.d {
                 Directions::UP => self.d = Directions::RIGHT,
                 Directions::RIGHT => self.d = Directions::DOWN,
@@ -201,30 +240,198 @@ impl Game {
     }
 
     fn dump_state(&self) {
-        for row in &self.map {
-            let row_str = row
-                .iter()
-                .map(|tile| match tile {
-                    Tile::Empty => ".",
-                    Tile::Obstacle => "#",
-                    Tile::Visited { dirs } => {
-                        if dirs.intersects(Directions::UP_DOWN)
-                            && dirs.intersects(Directions::LEFT_RIGTH)
-                        {
-                            "+"
-                        } else if dirs.intersects(Directions::UP_DOWN) {
-                            "|"
-                        } else if dirs.intersects(Directions::LEFT_RIGTH) {
-                            return "-";
-                        } else {
-                            "X"
-                        }
-                    }
-                })
-                .join("");
-            println!("{}", row_str);
+        print_frame(self);
+    }
+
+    // Steps needed to get back to `guard` walking cell by cell, for a guard known to be in a loop.
+    fn loop_length(&self, guard: Guard) -> usize {
+        let (mut x, mut y, mut d) = guard;
+        let mut length = 0;
+
+        loop {
+            let (dx, dy) = d.step();
+            if self.is_obstacle(x + dx, y + dy) {
+                d = d.turn_right();
+                continue;
+            }
+
+            (x, y) = (x + dx, y + dy);
+            length += 1;
+
+            if (x, y, d) == guard {
+                return length;
+            }
+        }
+    }
+
+    fn trace(&self, obstacle: Option<(i32, i32)>) -> PatrolTrace {
+        JumpTable::new(self).trace((self.x, self.y, self.d), obstacle)
+    }
+
+    // Patrol drawn with `|`, `-` and `+`, the loop with `*`, the cell where it closes with `X`, the
+    // added obstacle with `O` and the starting position with `^`.
+    fn render_ascii(&self, trace: &PatrolTrace) -> String {
+        let mut grid: Vec<Vec<char>> = self
+            .map
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|tile| if *tile == Tile::Obstacle { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+
+        for (segment, in_loop) in self.trace_segments(trace) {
+            for (x, y, d) in segment {
+                let cell = &mut grid[y as usize][x as usize];
+                let along = if d.intersects(Directions::UP_DOWN) {
+                    '|'
+                } else {
+                    '-'
+                };
+                *cell = match *cell {
+                    _ if in_loop => '*',
+                    '*' => '*',
+                    '.' => along,
+                    c if c == along => along,
+                    _ => '+',
+                };
+            }
+        }
+
+        if let SimulationResult::CycleDetected(witness) = trace.result {
+            let (x, y, _) = witness.closes_at;
+            grid[y as usize][x as usize] = 'X';
+        }
+        if let Some((x, y)) = trace.obstacle {
+            grid[y as usize][x as usize] = 'O';
+        }
+        grid[trace.start.1 as usize][trace.start.0 as usize] = '^';
+
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
+
+    fn render_svg(&self, trace: &PatrolTrace) -> String {
+        const CELL: i32 = 10;
+        let center = |v: i32| v * CELL + CELL / 2;
+        let rect = |svg: &mut String, x: i32, y: i32, fill: &str| {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="{}"/>"#,
+                x * CELL,
+                y * CELL,
+                fill
+            );
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+            self.col_count as i32 * CELL,
+            self.row_count as i32 * CELL
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+        for (y, row) in self.map.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if *tile == Tile::Obstacle {
+                    rect(&mut svg, x as i32, y as i32, "dimgray");
+                }
+            }
+        }
+        if let Some((x, y)) = trace.obstacle {
+            rect(&mut svg, x, y, "red");
+        }
+
+        for (segment, in_loop) in self.trace_segments(trace) {
+            let (Some(first), Some(last)) = (segment.first(), segment.last()) else {
+                continue;
+            };
+            let (color, width) = if in_loop {
+                ("crimson", 3)
+            } else {
+                ("steelblue", 2)
+            };
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                center(first.0),
+                center(first.1),
+                center(last.0),
+                center(last.1),
+                color,
+                width
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="green"/>"#,
+            center(trace.start.0),
+            center(trace.start.1),
+            CELL / 3
+        );
+        if let SimulationResult::CycleDetected(witness) = trace.result {
+            let (x, y, _) = witness.closes_at;
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="black" stroke-width="2"/>"#,
+                center(x),
+                center(y),
+                CELL / 2
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // The cells walked between consecutive turns, and whether they are part of the loop. A guard
+    // that leaves the map gets one last segment up to the edge.
+    fn trace_segments(&self, trace: &PatrolTrace) -> Vec<(Vec<Guard>, bool)> {
+        let mut points = vec![trace.start];
+        points.extend(trace.turns.iter().map(|(x, y, d)| (*x, *y, d.turn_right())));
+
+        let mut segments = vec![];
+        for (i, pair) in points.windows(2).enumerate() {
+            let in_loop = trace.loop_start.is_some_and(|start| i > start);
+            segments.push((self.walk(pair[0], (pair[1].0, pair[1].1)), in_loop));
+        }
+
+        let last = *points.last().unwrap();
+        match (trace.result, trace.loop_start) {
+            (SimulationResult::CycleDetected(_), Some(loop_start)) => {
+                let (x, y, _) = trace.turns[loop_start];
+                segments.push((self.walk(last, (x, y)), true));
+            }
+            _ => {
+                let (dx, dy) = last.2.step();
+                let (mut x, mut y) = (last.0, last.1);
+                while self.in_map(x + dx, y + dy) {
+                    (x, y) = (x + dx, y + dy);
+                }
+                segments.push((self.walk(last, (x, y)), false));
+            }
         }
-        println!("- {},{}", self.x, self.y);
+
+        segments
+    }
+
+    // Cells from `from` to `to` walking straight, both included.
+    fn walk(&self, from: Guard, to: (i32, i32)) -> Vec<Guard> {
+        let (mut x, mut y, d) = from;
+        let (dx, dy) = d.step();
+        let mut cells = vec![(x, y, d)];
+
+        while (x, y) != to {
+            (x, y) = (x + dx, y + dy);
+            cells.push((x, y, d));
+        }
+
+        cells
     }
 
     fn run_simulation(&mut self) -> SimulationResult {
@@ -237,10 +444,9 @@ impl Game {
         SimulationResult::GuardExited
     }
 
-    fn count_obstacles_that_produce_cycles(&mut self) -> usize {
+    fn count_obstacles_that_produce_cycles_brute_force(&self) -> usize {
         let mut result: usize = 0;
 
-        // Brute force version
         for col in 0..self.col_count as i32 {
             for row in 0..self.row_count as i32 {
                 if col == self.x && row == self.y {
@@ -252,45 +458,350 @@ impl Game {
                 if copy.is_empty(col, row) {
                     copy.set_tile_at(col, row, Tile::Obstacle);
 
-                    if copy.run_simulation() == SimulationResult::CycleDetected {
+                    if matches!(copy.run_simulation(), SimulationResult::CycleDetected(_)) {
                         result += 1;
                     }
                 }
             }
         }
 
-        // Intelligent version
-        // while self.guard_in_map() {
-        //     let mut copy = self.clone();
-        //     if copy.insert_obstacle() && (copy.run_simulation() == SimulationResult::CycleDetected)
-        //     {
-        //         result += 1;
-        //     }
-        //     self.tick();
-        // }
-
         result
     }
 
-    fn insert_obstacle(&mut self) -> bool {
-        let mut obs_x = self.x;
-        let mut obs_y = self.y;
+    // Only cells on the original patrol can change it, so those are the only candidates. Each one
+    // resumes from the guard state right before it is first reached, instead of from the start.
+    // Candidates are independent, so they are split between threads that share the jump table and
+    // keep their own record of visited turns.
+    fn obstacles_that_produce_cycles(&self) -> Vec<(i32, i32)> {
+        let jumps = JumpTable::new(self);
+        let candidates = self.obstacle_candidates();
 
-        match self.d {
-            Directions::UP => obs_y -= 1,
-            Directions::RIGHT => obs_x += 1,
-            Directions::DOWN => obs_y += 1,
-            Directions::LEFT => obs_x -= 1,
+        let thread_count = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = candidates.len().div_ceil(thread_count).max(1);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let jumps = &jumps;
+                    scope.spawn(move || {
+                        let mut seen = vec![Directions::empty(); self.col_count * self.row_count];
+                        chunk
+                            .iter()
+                            .filter(|(obstacle, guard)| {
+                                jumps.produces_cycle(*guard, Some(*obstacle), &mut seen)
+                            })
+                            .map(|(obstacle, _)| *obstacle)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("Loop search thread finished."))
+                .collect()
+        })
+    }
+
+    // Cells the guard walks through, each with the guard position and direction right before
+    // first stepping on it.
+    fn obstacle_candidates(&self) -> Vec<((i32, i32), Guard)> {
+        let mut candidates = vec![];
+        let mut reached = vec![vec![false; self.col_count]; self.row_count];
+        let (mut x, mut y, mut d) = (self.x, self.y, self.d);
+        reached[y as usize][x as usize] = true;
+
+        loop {
+            let (dx, dy) = d.step();
+            let (next_x, next_y) = (x + dx, y + dy);
+            if !self.in_map(next_x, next_y) {
+                break;
+            }
+
+            if self.is_obstacle(next_x, next_y) {
+                d = d.turn_right();
+                continue;
+            }
+
+            if !reached[next_y as usize][next_x as usize] {
+                reached[next_y as usize][next_x as usize] = true;
+                candidates.push(((next_x, next_y), (x, y, d)));
+            }
+
+            (x, y) = (next_x, next_y);
+        }
+
+        candidates
+    }
+}
+
+impl Directions {
+    fn turn_right(self) -> Directions {
+        match self {
+            Directions::UP => Directions::RIGHT,
+            Directions::RIGHT => Directions::DOWN,
+            Directions::DOWN => Directions::LEFT,
+            Directions::LEFT => Directions::UP,
+            _ => panic!("Unexpected current direction."),
+        }
+    }
+
+    fn step(self) -> (i32, i32) {
+        match self {
+            Directions::UP => (0, -1),
+            Directions::RIGHT => (1, 0),
+            Directions::DOWN => (0, 1),
+            Directions::LEFT => (-1, 0),
+            _ => panic!("Unexpected current direction."),
+        }
+    }
+
+    fn index(self) -> usize {
+        self.bits().trailing_zeros() as usize
+    }
+}
+
+// For every cell and direction, the cell where the guard stops walking straight because the next
+// one is an obstacle, or None if it walks off the map. Lets the guard move from turn to turn
+// instead of cell by cell.
+#[derive(Debug)]
+struct JumpTable {
+    col_count: usize,
+    jumps: Vec<[Option<(i32, i32)>; 4]>,
+}
+
+impl JumpTable {
+    fn new(game: &Game) -> JumpTable {
+        let col_count = game.col_count;
+        let row_count = game.row_count;
+        let mut jumps = vec![[None; 4]; col_count * row_count];
+
+        for col in 0..col_count as i32 {
+            let mut stop = None;
+            for row in 0..row_count as i32 {
+                if game.is_obstacle(col, row) {
+                    stop = Some((col, row + 1));
+                } else {
+                    jumps[row as usize * col_count + col as usize][Directions::UP.index()] = stop;
+                }
+            }
+
+            let mut stop = None;
+            for row in (0..row_count as i32).rev() {
+                if game.is_obstacle(col, row) {
+                    stop = Some((col, row - 1));
+                } else {
+                    jumps[row as usize * col_count + col as usize][Directions::DOWN.index()] = stop;
+                }
+            }
+        }
+
+        for row in 0..row_count as i32 {
+            let mut stop = None;
+            for col in 0..col_count as i32 {
+                if game.is_obstacle(col, row) {
+                    stop = Some((col + 1, row));
+                } else {
+                    jumps[row as usize * col_count + col as usize][Directions::LEFT.index()] = stop;
+                }
+            }
+
+            let mut stop = None;
+            for col in (0..col_count as i32).rev() {
+                if game.is_obstacle(col, row) {
+                    stop = Some((col - 1, row));
+                } else {
+                    jumps[row as usize * col_count + col as usize][Directions::RIGHT.index()] =
+                        stop;
+                }
+            }
+        }
+
+        JumpTable { col_count, jumps }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.col_count + x as usize
+    }
+
+    // Where the guard stops walking from (x, y) towards `d`, also taking into account an extra
+    // obstacle that is not in the table.
+    fn next_stop(
+        &self,
+        x: i32,
+        y: i32,
+        d: Directions,
+        extra: Option<(i32, i32)>,
+    ) -> Option<(i32, i32)> {
+        let stop = self.jumps[self.index(x, y)][d.index()];
+        let Some((ox, oy)) = extra else {
+            return stop;
+        };
+
+        let blocked_by_extra = match d {
+            Directions::UP => ox == x && oy < y && stop.is_none_or(|(_, sy)| oy >= sy),
+            Directions::DOWN => ox == x && oy > y && stop.is_none_or(|(_, sy)| oy <= sy),
+            Directions::LEFT => oy == y && ox < x && stop.is_none_or(|(sx, _)| ox >= sx),
+            Directions::RIGHT => oy == y && ox > x && stop.is_none_or(|(sx, _)| ox <= sx),
             _ => panic!("Unexpected current direction."),
         };
 
-        if !self.is_empty(obs_x, obs_y) {
-            return false;
+        if blocked_by_extra {
+            let (dx, dy) = d.step();
+            return Some((ox - dx, oy - dy));
+        }
+
+        stop
+    }
+
+    // Follows the guard from turn to turn. It is in a loop as soon as it stops at the same cell
+    // facing the same way twice. `seen` must be all empty, and is left that way on return.
+    fn produces_cycle(
+        &self,
+        guard: Guard,
+        obstacle: Option<(i32, i32)>,
+        seen: &mut [Directions],
+    ) -> bool {
+        let (mut x, mut y, mut d) = guard;
+        let mut turns = vec![];
+
+        let cycle = loop {
+            let Some((stop_x, stop_y)) = self.next_stop(x, y, d, obstacle) else {
+                break false;
+            };
+
+            let index = self.index(stop_x, stop_y);
+            if seen[index].intersects(d) {
+                break true;
+            }
+            seen[index] |= d;
+            turns.push(index);
+
+            (x, y, d) = (stop_x, stop_y, d.turn_right());
+        };
+
+        turns
+            .iter()
+            .for_each(|index| seen[*index] = Directions::empty());
+
+        cycle
+    }
+
+    // The guard one cell further, after turning while the way is blocked, like `Game::tick` moves
+    // it. Only for a guard in a loop that isn't boxed in, so it never walks off the map.
+    fn step(&self, guard: Guard, obstacle: Option<(i32, i32)>) -> Guard {
+        let (x, y, mut d) = guard;
+        while self.next_stop(x, y, d, obstacle) == Some((x, y)) {
+            d = d.turn_right();
+        }
+
+        let (dx, dy) = d.step();
+        (x + dx, y + dy, d)
+    }
+
+    fn trace(&self, start: Guard, obstacle: Option<(i32, i32)>) -> PatrolTrace {
+        let (mut x, mut y, mut d) = start;
+        let mut turns = vec![];
+        let mut first_seen = HashMap::new();
+
+        let (loop_start, result) = loop {
+            let Some((stop_x, stop_y)) = self.next_stop(x, y, d, obstacle) else {
+                break (None, SimulationResult::GuardExited);
+            };
+
+            let turn = (stop_x, stop_y, d);
+            if let Some(&loop_start) = first_seen.get(&turn) {
+                let length = turns[loop_start..]
+                    .iter()
+                    .chain([&turn])
+                    .tuple_windows()
+                    .map(|(a, b): (&Guard, &Guard)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
+                    .sum::<u32>() as usize;
+
+                // The guard can already be going around the loop on its way to that turn. Walking
+                // from the turn before it, the first cell and direction that comes back `length`
+                // steps later is where the loop closes.
+                let mut closes_at = if loop_start == 0 {
+                    start
+                } else {
+                    turns[loop_start - 1]
+                };
+                if length > 0 {
+                    let mut ahead =
+                        (0..length).fold(closes_at, |guard, _| self.step(guard, obstacle));
+                    while closes_at != ahead {
+                        closes_at = self.step(closes_at, obstacle);
+                        ahead = self.step(ahead, obstacle);
+                    }
+                } else {
+                    closes_at = turn;
+                }
+
+                let witness = LoopWitness { closes_at, length };
+                break (Some(loop_start), SimulationResult::CycleDetected(witness));
+            }
+
+            first_seen.insert(turn, turns.len());
+            turns.push(turn);
+            (x, y, d) = (stop_x, stop_y, d.turn_right());
+        };
+
+        PatrolTrace {
+            start,
+            obstacle,
+            turns,
+            loop_start,
+            result,
+        }
+    }
+}
+
+impl Scene for Game {
+    fn width(&self) -> usize {
+        self.col_count
+    }
+
+    fn height(&self) -> usize {
+        self.row_count
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        if self.guard_in_map() && (x as i32, y as i32) == (self.x, self.y) {
+            let glyph = match self.d {
+                Directions::UP => '^',
+                Directions::RIGHT => '>',
+                Directions::DOWN => 'v',
+                _ => '<',
+            };
+            return Cell::new(glyph, Color::Yellow);
         }
 
-        self.set_tile_at(obs_x, obs_y, Tile::Obstacle);
+        match self.map[y][x] {
+            Tile::Empty => Cell::new('.', Color::DarkGrey),
+            Tile::Obstacle => Cell::new('#', Color::Grey),
+            Tile::Visited { dirs } => {
+                let glyph = if dirs.intersects(Directions::UP_DOWN)
+                    && dirs.intersects(Directions::LEFT_RIGTH)
+                {
+                    '+'
+                } else if dirs.intersects(Directions::UP_DOWN) {
+                    '|'
+                } else {
+                    '-'
+                };
+                Cell::new(glyph, Color::Cyan)
+            }
+        }
+    }
+
+    fn status(&self) -> String {
+        format!("Guard at {},{}", self.x, self.y)
+    }
 
-        true
+    fn focus(&self) -> Option<(usize, usize)> {
+        self.guard_in_map()
+            .then_some((self.x as usize, self.y as usize))
     }
 }
 
@@ -307,29 +818,102 @@ fn convert_row(row: &str) -> Vec<Tile> {
     row
 }
 
+fn parse(input: &str) -> Result<Game> {
+    ensure!(
+        input.chars().all(|c| ".#^\n".contains(c)),
+        "The map can only have '.', '#' and '^'"
+    );
+    ensure!(
+        input.matches('^').count() == 1,
+        "Expected a single guard on the map"
+    );
+    let width = input.lines().next().map_or(0, str::len);
+    ensure!(
+        input.lines().all(|line| line.len() == width),
+        "Map rows have different lengths"
+    );
+
+    let mut lines = input.lines();
+    let mut game = Game::new(lines.next().context("The map is empty")?.to_owned());
+    lines.for_each(|line| game.push_row(line.to_owned()));
+    ensure!(game.guard_in_map(), "There is no guard on the map");
+
+    Ok(game)
+}
+
+fn part1(game: &Game) -> Result<usize> {
+    let mut game = game.clone();
+    ensure!(
+        game.run_simulation() == SimulationResult::GuardExited,
+        "The guard never leaves the map"
+    );
+
+    Ok(game.count_visited())
+}
+
+fn part2(game: &Game) -> Result<usize> {
+    // Candidates come from walking the original patrol, which has to end.
+    ensure!(
+        game.clone().run_simulation() == SimulationResult::GuardExited,
+        "The guard never leaves the map"
+    );
+
+    Ok(game.obstacles_that_produce_cycles().len())
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
-    //region Part 1
-    println!("=== Part 1 ===");
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let first_row = lines.next().unwrap();
-        let mut game = Game::new(first_row);
+    // cargo run --release --bin 06 -- --trace 12,34 [--svg trace.svg]
+    // cargo run --release --bin 06 -- --animate 30
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(fps) = fps_from_args(&args)? {
+        let mut game = parse(&fs::read_to_string(INPUT_FILE)?)?;
+        return Animation::new(fps).run(&mut game, |game| {
+            game.guard_in_map() && game.tick().is_none()
+        });
+    }
+    if let Some(position) = args.iter().position(|arg| arg == "--trace") {
+        let obstacle = args
+            .get(position + 1)
+            .and_then(|value| value.split_once(','))
+            .map(|(x, y)| Ok((x.parse::<i32>()?, y.parse::<i32>()?)))
+            .context("Expected an obstacle position like --trace 12,34")??;
+        let svg = args
+            .iter()
+            .position(|arg| arg == "--svg")
+            .and_then(|position| args.get(position + 1));
 
-        for line in lines {
-            game.push_row(line);
-        }
+        let game = parse(&fs::read_to_string(INPUT_FILE)?)?;
+        ensure!(
+            game.is_empty(obstacle.0, obstacle.1),
+            "No room for an obstacle there"
+        );
 
-        assert_eq!(SimulationResult::GuardExited, game.run_simulation());
+        let trace = game.trace(Some(obstacle));
+        print!("{}", game.render_ascii(&trace));
+        println!("{:?}", trace.result);
+        if let Some(path) = svg {
+            fs::write(path, game.render_svg(&trace))?;
+        }
 
-        Ok(game.count_visited())
+        return Ok(());
     }
-    assert_eq!(41, part1(BufReader::new(TEST.as_bytes()))?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    //region Part 1
+    println!("=== Part 1 ===");
+    assert_eq!(41, part1(&parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     assert_eq!(5080, result);
     //endregion
@@ -337,25 +921,132 @@ fn main() -> Result<()> {
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let first_row = lines.next().unwrap();
-        let mut game = Game::new(first_row);
+    assert_eq!(6, part2(&parse(TEST)?)?);
 
-        for line in lines {
-            game.push_row(line);
-        }
+    let game = parse(TEST)?;
+    assert_eq!(6, game.count_obstacles_that_produce_cycles_brute_force());
 
-        Ok(game.count_obstacles_that_produce_cycles())
-    }
+    let trace = game.trace(None);
+    assert_eq!(SimulationResult::GuardExited, trace.result);
+    assert_eq!(None, trace.loop_start);
+    assert_eq!(
+        vec![
+            (4, 1),
+            (8, 1),
+            (8, 6),
+            (2, 6),
+            (2, 4),
+            (6, 4),
+            (6, 8),
+            (1, 8),
+            (1, 7),
+            (7, 7)
+        ],
+        trace
+            .turns
+            .iter()
+            .map(|(x, y, _)| (*x, *y))
+            .collect::<Vec<_>>()
+    );
 
-    assert_eq!(6, part2(BufReader::new(TEST.as_bytes()))?);
+    let trace = game.trace(Some((3, 6)));
+    let mut copy = game.clone();
+    copy.set_tile_at(3, 6, Tile::Obstacle);
+    let SimulationResult::CycleDetected(witness) = trace.result else {
+        bail!("Expected a loop with an obstacle at 3,6");
+    };
+    let SimulationResult::CycleDetected(cell_witness) = copy.run_simulation() else {
+        bail!("Expected a loop with an obstacle at 3,6");
+    };
+    assert_eq!(Some(0), trace.loop_start);
+    assert_eq!((4, 5, Directions::UP), witness.closes_at);
+    assert_eq!(cell_witness, witness);
+    assert_eq!(
+        "\
+....#.....
+....*****#
+....*...*.
+..#.*...*.
+....*..#*.
+....X...*.
+.#.O^****.
+........#.
+#.........
+......#...
+",
+        game.render_ascii(&trace)
+    );
+    assert_eq!(
+        vec![(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)],
+        game.obstacles_that_produce_cycles()
+            .into_iter()
+            .sorted_by_key(|(x, y)| (*y, *x))
+            .collect::<Vec<_>>()
+    );
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     assert_eq!(1919, result);
     //endregion
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Small maps with scattered obstacles and the guard facing up somewhere.
+    fn map() -> impl Strategy<Value = String> {
+        (1..10usize, 1..10usize).prop_flat_map(|(width, height)| {
+            (
+                prop::collection::vec(prop::bool::weighted(0.2), width * height),
+                0..width * height,
+            )
+                .prop_map(move |(obstacles, guard)| {
+                    let mut map = String::new();
+                    for (i, obstacle) in obstacles.into_iter().enumerate() {
+                        map.push(match (i == guard, obstacle) {
+                            (true, _) => '^',
+                            (false, true) => '#',
+                            (false, false) => '.',
+                        });
+                        if (i + 1) % width == 0 {
+                            map.push('\n');
+                        }
+                    }
+                    map
+                })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn loop_search_matches_simulating_every_obstacle(map in map()) {
+            let game = parse(&map).unwrap();
+            // The puzzle's guard always leaves, the search relies on it.
+            prop_assume!(game.clone().run_simulation() == SimulationResult::GuardExited);
+
+            prop_assert_eq!(
+                game.count_obstacles_that_produce_cycles_brute_force(),
+                game.obstacles_that_produce_cycles().len()
+            );
+        }
+
+        #[test]
+        fn traces_match_the_cell_simulation(map in map()) {
+            let game = parse(&map).unwrap();
+            prop_assert_eq!(game.clone().run_simulation(), game.trace(None).result);
+
+            for (x, y) in (0..game.col_count as i32).cartesian_product(0..game.row_count as i32) {
+                if !game.is_empty(x, y) || (x, y) == (game.x, game.y) {
+                    continue;
+                }
+                let mut copy = game.clone();
+                copy.set_tile_at(x, y, Tile::Obstacle);
+                prop_assert_eq!(copy.run_simulation(), game.trace(Some((x, y))).result);
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "07";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+// Every combination of operators gets tried, so equations can't be much longer than the real ones.
+const MAX_NUMBERS: usize = 13;
 
 const TEST: &str = "\
 190: 10 19
@@ -33,125 +35,143 @@ enum Operation2 {
     Concatenate,
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+struct Equation {
+    total: usize,
+    parts: Vec<usize>,
+}
 
-    //region Part 1
-    println!("=== Part 1 ===");
+fn parse(input: &str) -> Result<Vec<Equation>> {
+    input
+        .lines()
+        .map(|line| {
+            let (total, parts) = line
+                .split_once(':')
+                .with_context(|| format!("Missing test value in [{}]", line))?;
+            let parts = parts
+                .split_whitespace()
+                .map(|n| n.parse::<usize>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .with_context(|| format!("Invalid numbers in [{}]", line))?;
+            ensure!(
+                parts.len() >= 2,
+                "Expected at least two numbers in [{}]",
+                line
+            );
+            ensure!(parts.len() <= MAX_NUMBERS, "Too many numbers in [{}]", line);
+
+            Ok(Equation {
+                total: total
+                    .parse()
+                    .with_context(|| format!("Invalid test value in [{}]", line))?,
+                parts,
+            })
+        })
+        .collect()
+}
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut sum_of_test_values: usize = 0;
-        for line in reader.lines().map_while(Result::ok) {
-            let mut line_split = line.split_whitespace();
-            let total = line_split
-                .next()
-                .expect("Expected first number in line")
-                .split(":")
-                .next()
-                .expect("Number before :")
-                .parse::<usize>()
-                .unwrap();
-
-            let parts: Vec<usize> = line_split.flat_map(|n| n.parse::<usize>()).collect();
-            let mut operations = vec![Operation::Sum; parts.len() - 1];
-            let mut missing_mutations = true;
-            while missing_mutations {
-                let mut result = 0;
-
-                match operations[0] {
-                    Operation::Sum => result += parts[0] + parts[1],
-                    Operation::Multiply => result += parts[0] * parts[1],
-                }
+fn part1(equations: &[Equation]) -> Result<usize> {
+    let mut sum_of_test_values: usize = 0;
+    for Equation { total, parts } in equations.iter() {
+        let total = *total;
+        let mut operations = vec![Operation::Sum; parts.len() - 1];
+        let mut missing_mutations = true;
+        while missing_mutations {
+            // None once the result no longer fits, so it can't match the test value.
+            let mut result = match operations[0] {
+                Operation::Sum => parts[0].checked_add(parts[1]),
+                Operation::Multiply => parts[0].checked_mul(parts[1]),
+            };
 
-                for i in 1..operations.len() {
-                    match operations[i] {
-                        Operation::Sum => result += parts[i + 1],
-                        Operation::Multiply => result *= parts[i + 1],
-                    }
-                }
+            for i in 1..operations.len() {
+                result = result.and_then(|result| match operations[i] {
+                    Operation::Sum => result.checked_add(parts[i + 1]),
+                    Operation::Multiply => result.checked_mul(parts[i + 1]),
+                });
+            }
 
-                if result == total {
-                    sum_of_test_values += total;
-                    break;
-                }
+            if result == Some(total) {
+                sum_of_test_values += total;
+                break;
+            }
+
+            missing_mutations = mutate_operations(&mut operations);
+        }
+    }
+    Ok(sum_of_test_values)
+}
+
+fn part2(equations: &[Equation]) -> Result<usize> {
+    let mut sum_of_test_values: usize = 0;
+    for Equation { total, parts } in equations.iter() {
+        let total = *total;
+        let mut operations = vec![Operation2::Sum; parts.len() - 1];
+        let mut missing_mutations = true;
+        while missing_mutations {
+            let mut result = match operations[0] {
+                Operation2::Sum => parts[0].checked_add(parts[1]),
+                Operation2::Multiply => parts[0].checked_mul(parts[1]),
+                Operation2::Concatenate => concatenate(parts[0], parts[1]),
+            };
 
-                missing_mutations = mutate_operations(&mut operations);
+            for i in 1..operations.len() {
+                result = result.and_then(|result| match operations[i] {
+                    Operation2::Sum => result.checked_add(parts[i + 1]),
+                    Operation2::Multiply => result.checked_mul(parts[i + 1]),
+                    Operation2::Concatenate => concatenate(result, parts[i + 1]),
+                });
             }
+            if result == Some(total) {
+                sum_of_test_values += total;
+                break;
+            }
+
+            missing_mutations = mutate_operations2(&mut operations);
         }
-        Ok(sum_of_test_values)
     }
+    Ok(sum_of_test_values)
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
 
-    assert_eq!(3749, part1(BufReader::new(TEST.as_bytes()))?);
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |equations| part1(equations))?;
+        bench(DAY, 2, parse, |equations| part2(equations))?;
+        return Ok(());
+    }
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(3749, part1(&parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut sum_of_test_values: usize = 0;
-        for line in reader.lines().map_while(Result::ok) {
-            let mut line_split = line.split_whitespace();
-            let total = line_split
-                .next()
-                .expect("Expected first number in line")
-                .split(":")
-                .next()
-                .expect("Number before :")
-                .parse::<usize>()
-                .unwrap();
-
-            let parts: Vec<usize> = line_split.flat_map(|n| n.parse::<usize>()).collect();
-            let mut operations = vec![Operation2::Sum; parts.len() - 1];
-            let mut missing_mutations = true;
-            while missing_mutations {
-                let mut result;
-
-                match operations[0] {
-                    Operation2::Sum => result = parts[0] + parts[1],
-                    Operation2::Multiply => result = parts[0] * parts[1],
-                    Operation2::Concatenate => result = concatenate(parts[0], parts[1]),
-                }
+    assert_eq!(11387, part2(&parse(TEST)?)?);
 
-                for i in 1..operations.len() {
-                    match operations[i] {
-                        Operation2::Sum => result += parts[i + 1],
-                        Operation2::Multiply => result *= parts[i + 1],
-                        Operation2::Concatenate => result = concatenate(result, parts[i + 1]),
-                    }
-                }
-                if result == total {
-                    sum_of_test_values += total;
-                    break;
-                }
-
-                missing_mutations = mutate_operations2(&mut operations);
-            }
-        }
-        Ok(sum_of_test_values)
-    }
-
-    assert_eq!(11387, part2(BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
     Ok(())
 }
 
-fn concatenate(a: usize, b: usize) -> usize {
+fn concatenate(a: usize, b: usize) -> Option<usize> {
     let astr = a.to_string();
     let bstr = b.to_string();
 
     let mut result: String = astr.clone();
     result.push_str(&bstr);
 
-    result.parse::<usize>().expect("Number is parsable")
+    result.parse::<usize>().ok()
 }
 
 fn mutate_operations(operations: &mut [Operation]) -> bool {
@@ -163,13 +183,13 @@ fn mutate_operations(operations: &mut [Operation]) -> bool {
         Operation::Sum => operations[0] = Operation::Multiply,
         Operation::Multiply => {
             operations[0] = Operation::Sum;
-            for i in 1..operations.len() {
-                match operations[i] {
+            for operation in operations.iter_mut().skip(1) {
+                match operation {
                     Operation::Sum => {
-                        operations[i] = Operation::Multiply;
+                        *operation = Operation::Multiply;
                         break;
                     }
-                    Operation::Multiply => operations[i] = Operation::Sum,
+                    Operation::Multiply => *operation = Operation::Sum,
                 }
             }
         }
@@ -187,17 +207,17 @@ fn mutate_operations2(operations: &mut [Operation2]) -> bool {
         Operation2::Multiply => operations[0] = Operation2::Concatenate,
         Operation2::Concatenate => {
             operations[0] = Operation2::Sum;
-            for i in 1..operations.len() {
-                match operations[i] {
+            for operation in operations.iter_mut().skip(1) {
+                match operation {
                     Operation2::Sum => {
-                        operations[i] = Operation2::Multiply;
+                        *operation = Operation2::Multiply;
                         break;
                     }
                     Operation2::Multiply => {
-                        operations[i] = Operation2::Concatenate;
+                        *operation = Operation2::Concatenate;
                         break;
                     }
-                    Operation2::Concatenate => operations[i] = Operation2::Sum,
+                    Operation2::Concatenate => *operation = Operation2::Sum,
                 }
             }
         }
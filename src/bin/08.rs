@@ -1,9 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::BTreeMap;
+use std::fs;
 
 const DAY: &str = "08";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -23,200 +24,250 @@ const TEST: &str = "\
 ............
 ";
 
+const TEST_GCD: &str = "\
+a....
+.....
+....a
+.....
+.....
+";
+
 #[derive(Debug)]
 struct Map {
-    map: Vec<Vec<char>>,
     col_count: usize,
     row_count: usize,
-    antinode: Vec<Vec<char>>,
+    antennas: BTreeMap<char, Vec<(i32, i32)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // One antinode on each side of a pair, as far from the closest antenna as they are apart.
+    Pair,
+    // Every grid point in line with a pair.
+    Resonant,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct AntinodeReport {
+    total: usize,
+    per_frequency: BTreeMap<char, usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    fn new(size: usize) -> Bitset {
+        Bitset {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    fn union_with(&mut self, other: &Bitset) {
+        self.words
+            .iter_mut()
+            .zip(other.words.iter())
+            .for_each(|(word, other)| *word |= other);
+    }
+
+    fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
 }
 
 impl Map {
     fn new(first_row: String) -> Map {
-        Map {
-            map: vec![first_row.chars().collect()],
+        let mut map = Map {
             col_count: first_row.len(),
-            row_count: 1,
-            antinode: vec![vec!['.'; first_row.len()]],
-        }
-    }
+            row_count: 0,
+            antennas: BTreeMap::new(),
+        };
+        map.push_row(first_row);
 
-    fn push_row(&mut self, row: String) {
-        self.map.push(row.chars().collect());
-        self.antinode.push(vec!['.'; self.col_count]);
-        self.row_count += 1;
+        map
     }
 
-    fn count_antinodes(&self) -> usize {
-        let mut count = 0;
-        for row in &self.antinode {
-            for char in row {
-                if *char == '#' {
-                    count += 1;
-                }
+    fn push_row(&mut self, row: String) {
+        for (col, c) in row.chars().enumerate() {
+            if c != '.' && c != '#' {
+                self.antennas
+                    .entry(c)
+                    .or_default()
+                    .push((self.row_count as i32, col as i32));
             }
         }
+        self.row_count += 1;
+    }
 
-        count
+    fn in_map(&self, row: i32, col: i32) -> bool {
+        row >= 0 && (row as usize) < self.row_count && col >= 0 && (col as usize) < self.col_count
     }
 
-    fn value_at(&self, row: usize, col: usize) -> char {
-        self.map[row][col]
+    fn index(&self, row: i32, col: i32) -> usize {
+        row as usize * self.col_count + col as usize
     }
 
-    fn add_antinodes(&mut self, row_1: usize, col_1: usize, row_2: usize, col_2: usize) {
-        let row_diff: i32 = row_2 as i32 - row_1 as i32;
-        let col_diff: i32 = col_2 as i32 - col_1 as i32;
+    fn antinodes(&self, mode: Mode) -> AntinodeReport {
+        let mut all = Bitset::new(self.row_count * self.col_count);
+        let mut per_frequency = BTreeMap::new();
+
+        for (frequency, antennas) in self.antennas.iter() {
+            let mut antinodes = Bitset::new(self.row_count * self.col_count);
+
+            for (i, &(row_1, col_1)) in antennas.iter().enumerate() {
+                for &(row_2, col_2) in antennas[i + 1..].iter() {
+                    let row_diff = row_2 - row_1;
+                    let col_diff = col_2 - col_1;
+
+                    match mode {
+                        Mode::Pair => {
+                            self.add_antinode_at(
+                                &mut antinodes,
+                                row_1 - row_diff,
+                                col_1 - col_diff,
+                            );
+                            self.add_antinode_at(
+                                &mut antinodes,
+                                row_2 + row_diff,
+                                col_2 + col_diff,
+                            );
+                        }
+                        Mode::Resonant => {
+                            // Reduced so that grid points between the antennas are not skipped.
+                            let divisor = gcd(row_diff.abs(), col_diff.abs());
+                            let (row_step, col_step) = (row_diff / divisor, col_diff / divisor);
+
+                            self.add_antinodes_steps(
+                                &mut antinodes,
+                                row_1,
+                                col_1,
+                                row_step,
+                                col_step,
+                            );
+                            self.add_antinodes_steps(
+                                &mut antinodes,
+                                row_1,
+                                col_1,
+                                -row_step,
+                                -col_step,
+                            );
+                        }
+                    }
+                }
+            }
 
-        self.add_antinode_at(row_1 as i32 - row_diff, col_1 as i32 - col_diff);
-        self.add_antinode_at(row_2 as i32 + row_diff, col_2 as i32 + col_diff);
-    }
+            per_frequency.insert(*frequency, antinodes.count());
+            all.union_with(&antinodes);
+        }
 
-    fn add_antinode_at(&mut self, row: i32, col: i32) -> bool {
-        if row < 0 || row as usize >= self.row_count {
-            return false;
+        AntinodeReport {
+            total: all.count(),
+            per_frequency,
         }
+    }
 
-        if col < 0 || col as usize >= self.col_count {
+    fn add_antinode_at(&self, antinodes: &mut Bitset, row: i32, col: i32) -> bool {
+        if !self.in_map(row, col) {
             return false;
         }
 
-        self.antinode[row as usize][col as usize] = '#';
+        antinodes.insert(self.index(row, col));
 
         true
     }
 
-    fn add_antinodes_2(&mut self, row_1: usize, col_1: usize, row_2: usize, col_2: usize) {
-        let row_diff: i32 = row_2 as i32 - row_1 as i32;
-        let col_diff: i32 = col_2 as i32 - col_1 as i32;
-
-        self.add_antinodes_2_steps(row_1 as i32, col_1 as i32, -row_diff, -col_diff);
-        self.add_antinodes_2_steps(row_2 as i32, col_2 as i32, row_diff, col_diff);
+    fn add_antinodes_steps(
+        &self,
+        antinodes: &mut Bitset,
+        row: i32,
+        col: i32,
+        row_step: i32,
+        col_step: i32,
+    ) {
+        let mut r = row;
+        let mut c = col;
+        while self.add_antinode_at(antinodes, r, c) {
+            r += row_step;
+            c += col_step;
+        }
     }
+}
 
-    fn dump_state(&self) {
-        self.map.iter().for_each(|row| {
-            println!("{}", row.iter().collect::<String>());
-        });
-        println!()
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    fn add_antinodes_2_steps(&mut self, row: i32, col: i32, row_diff: i32, col_diff: i32) {
-        let mut r = row - row_diff;
-        let mut c = col - col_diff;
-        while self.add_antinode_at(r, c) {
-            r -= row_diff;
-            c -= col_diff;
-        }
-    }
+fn part1(map: &Map) -> Result<usize> {
+    Ok(map.antinodes(Mode::Pair).total)
+}
+
+fn part2(map: &Map) -> Result<usize> {
+    Ok(map.antinodes(Mode::Resonant).total)
 }
 
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        // Read map
-        let mut lines = reader.lines().map_while(Result::ok);
-        let first_row = lines.next().unwrap();
-        let mut map = Map::new(first_row);
-
-        for line in lines {
-            map.push_row(line);
-        }
-
-        // for the whole map
-        for row in 0..map.row_count {
-            for col in 0..map.col_count {
-                // find antenna symbol (ignore "." and "#")
-                match map.value_at(row, col) {
-                    '.' => continue,
-                    '#' => continue,
-                    antenna => {
-                        // For reminder of map
-                        //  Find same antenna symbol
-                        for c in col + 1..map.col_count {
-                            if antenna == map.value_at(row, c) {
-                                map.add_antinodes(row, col, row, c);
-                            }
-                        }
-                        for r in row + 1..map.row_count {
-                            for c in 0..map.col_count {
-                                if antenna == map.value_at(r, c) {
-                                    map.add_antinodes(row, col, r, c);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Count antinodes
-        let count = map.count_antinodes();
-        Ok(count)
-    }
+    assert_eq!(14, part1(&parse(TEST)?)?);
+    assert_eq!(
+        BTreeMap::from([('0', 10), ('A', 5)]),
+        parse(TEST)?.antinodes(Mode::Pair).per_frequency
+    );
+    assert_eq!(0, part1(&parse(TEST_GCD)?)?);
 
-    assert_eq!(14, part1(BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        // Read map
-        let mut lines = reader.lines().map_while(Result::ok);
-        let first_row = lines.next().unwrap();
-        let mut map = Map::new(first_row);
-
-        for line in lines {
-            map.push_row(line);
-        }
-
-        // for the whole map
-        for row in 0..map.row_count {
-            for col in 0..map.col_count {
-                // find antenna symbol (ignore "." and "#")
-                match map.value_at(row, col) {
-                    '.' => continue,
-                    '#' => continue,
-                    antenna => {
-                        // For reminder of map
-                        //  Find same antenna symbol
-                        for c in col + 1..map.col_count {
-                            if antenna == map.value_at(row, c) {
-                                map.add_antinodes_2(row, col, row, c);
-                            }
-                        }
-                        for r in row + 1..map.row_count {
-                            for c in 0..map.col_count {
-                                if antenna == map.value_at(r, c) {
-                                    map.add_antinodes_2(row, col, r, c);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Count antinodes
-        let count = map.count_antinodes();
-        Ok(count)
-    }
+    assert_eq!(34, part2(&parse(TEST)?)?);
+    assert_eq!(
+        BTreeMap::from([('0', 21), ('A', 16)]),
+        parse(TEST)?.antinodes(Mode::Resonant).per_frequency
+    );
+    assert_eq!(3, part2(&parse(TEST_GCD)?)?);
 
-    assert_eq!(34, part2(BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
     Ok(())
 }
+
+fn parse(input: &str) -> Result<Map> {
+    let mut lines = input.lines();
+    let mut map = Map::new(lines.next().context("The map is empty")?.to_owned());
+
+    for line in lines {
+        map.push_row(line.to_owned());
+    }
+
+    Ok(map)
+}
@@ -1,9 +1,9 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "09";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -12,165 +12,163 @@ const TEST: &str = "\
 2333133121414131402
 ";
 
-fn main() -> Result<()> {
-    start_day(DAY);
-
-    //region Part 1
-    println!("=== Part 1 ===");
-
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut disk: Vec<i16> = vec![];
+// The size of each file and free space, one digit each.
+fn parse(input: &str) -> Result<Vec<usize>> {
+    input
+        .lines()
+        .flat_map(|line| line.chars())
+        .map(|c| {
+            c.to_digit(10)
+                .map(|size| size as usize)
+                .with_context(|| format!("Expected a digit, got [{}]", c))
+        })
+        .collect()
+}
 
-        let mut length_or_freespace = true;
-        let mut field_id: i16 = 0;
+fn part1(sizes: &[usize]) -> Result<usize> {
+    let mut disk: Vec<Option<usize>> = vec![];
 
-        for line in reader.lines().map_while(Result::ok) {
-            for c in line.chars() {
-                let size = c
-                    .to_string()
-                    .parse::<usize>()
-                    .expect("Digit is expected in input");
+    let mut length_or_freespace = true;
+    let mut field_id: usize = 0;
 
-                if length_or_freespace {
-                    for _ in 0..size {
-                        disk.push(field_id);
-                    }
+    for &size in sizes {
+        if length_or_freespace {
+            for _ in 0..size {
+                disk.push(Some(field_id));
+            }
 
-                    field_id += 1;
-                } else {
-                    for _ in 0..size {
-                        disk.push(-1);
-                    }
-                }
-                length_or_freespace = !length_or_freespace;
+            field_id += 1;
+        } else {
+            for _ in 0..size {
+                disk.push(None);
             }
         }
+        length_or_freespace = !length_or_freespace;
+    }
 
-        let mut last_block = disk.len() - 1;
-        let mut next_free = 0;
-        while disk[next_free] != -1 {
-            next_free += 1;
-        }
+    let mut next_free = disk.iter().position(|b| b.is_none()).unwrap_or(disk.len());
+    let mut last_block = disk.iter().rposition(|b| b.is_some()).unwrap_or(0);
 
-        while disk[last_block] == -1 {
-            last_block -= 1;
-        }
+    while next_free < last_block {
+        disk[next_free] = disk[last_block].take();
+        next_free += 1;
+        last_block -= 1;
 
-        while next_free < last_block {
-            disk[next_free] = disk[last_block];
-            disk[last_block] = -1;
+        while next_free < last_block && disk[next_free].is_some() {
             next_free += 1;
+        }
+        while next_free < last_block && disk[last_block].is_none() {
             last_block -= 1;
-
-            while disk[next_free] != -1 {
-                next_free += 1;
-            }
-            while disk[last_block] == -1 {
-                last_block -= 1;
-            }
         }
+    }
 
-        let mut total = 0;
+    let mut total = 0;
 
-        for (i, v) in disk.iter().enumerate() {
-            if *v != -1 {
-                total += i * *v as usize;
-            }
+    for (i, v) in disk.iter().enumerate() {
+        if let Some(id) = v {
+            total += i * id;
         }
-
-        Ok(total)
     }
 
-    assert_eq!(1928, part1(BufReader::new(TEST.as_bytes()))?);
+    Ok(total)
+}
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
-    println!("Result = {}", result);
-    //endregion
+fn part2(sizes: &[usize]) -> Result<usize> {
+    let mut disk: Vec<DiskPart> = vec![];
 
-    //region Part 2
-    println!("\n=== Part 2 ===");
+    let mut length_or_freespace = true;
+    let mut file_id: usize = 0;
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut disk: Vec<DiskPart> = vec![];
-
-        let mut length_or_freespace = true;
-        let mut file_id: usize = 0;
-
-        for line in reader.lines().map_while(Result::ok) {
-            for c in line.chars() {
-                let size = c
-                    .to_string()
-                    .parse::<usize>()
-                    .expect("Digit is expected in input");
-
-                if length_or_freespace {
-                    disk.push(DiskPart::DiskFile {
-                        id: file_id,
-                        length: size,
-                    });
-                    file_id += 1;
-                } else {
-                    disk.push(DiskPart::FreeSpace { length: size });
-                }
-                length_or_freespace = !length_or_freespace;
-            }
+    for &size in sizes {
+        if length_or_freespace {
+            disk.push(DiskPart::DiskFile {
+                id: file_id,
+                length: size,
+            });
+            file_id += 1;
+        } else {
+            disk.push(DiskPart::FreeSpace { length: size });
         }
+        length_or_freespace = !length_or_freespace;
+    }
 
-        for file_index in (0..disk.len()).rev() {
-            if let DiskPart::DiskFile {
-                id: file_id,
-                length: file_length,
-            } = disk[file_index]
-            {
-                for free_space_index in 0..file_index {
-                    if let DiskPart::FreeSpace { length } = disk[free_space_index] {
-                        if length >= file_length {
-                            disk[file_index] = DiskPart::FreeSpace {
-                                length: file_length,
-                            };
-
-                            disk[free_space_index] = DiskPart::DiskFile {
-                                id: file_id,
-                                length: file_length,
-                            };
-
-                            let reminder = length - file_length;
-                            if reminder > 0 {
-                                disk.insert(
-                                    free_space_index + 1,
-                                    DiskPart::FreeSpace { length: reminder },
-                                );
-                            }
-
-                            break;
+    for file_index in (0..disk.len()).rev() {
+        if let DiskPart::DiskFile {
+            id: file_id,
+            length: file_length,
+        } = disk[file_index]
+        {
+            for free_space_index in 0..file_index {
+                if let DiskPart::FreeSpace { length } = disk[free_space_index] {
+                    if length >= file_length {
+                        disk[file_index] = DiskPart::FreeSpace {
+                            length: file_length,
+                        };
+
+                        disk[free_space_index] = DiskPart::DiskFile {
+                            id: file_id,
+                            length: file_length,
+                        };
+
+                        let reminder = length - file_length;
+                        if reminder > 0 {
+                            disk.insert(
+                                free_space_index + 1,
+                                DiskPart::FreeSpace { length: reminder },
+                            );
                         }
+
+                        break;
                     }
                 }
             }
         }
+    }
 
-        let mut total = 0;
-        for (index, block) in disk
-            .iter()
-            .flat_map(|x| match x {
-                DiskPart::FreeSpace { length } => vec![None; *length],
-                DiskPart::DiskFile { id, length } => vec![Some(*id); *length],
-            })
-            .enumerate()
-        {
-            if let Some(id) = block {
-                total += index * id;
-            }
+    let mut total = 0;
+    for (index, block) in disk
+        .iter()
+        .flat_map(|x| match x {
+            DiskPart::FreeSpace { length } => vec![None; *length],
+            DiskPart::DiskFile { id, length } => vec![Some(*id); *length],
+        })
+        .enumerate()
+    {
+        if let Some(id) = block {
+            total += index * id;
         }
+    }
+
+    Ok(total)
+}
 
-        Ok(total)
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |sizes| part1(sizes))?;
+        bench(DAY, 2, parse, |sizes| part2(sizes))?;
+        return Ok(());
     }
 
-    assert_eq!(2858, part2(BufReader::new(TEST.as_bytes()))?);
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(1928, part1(&parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!(2858, part2(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
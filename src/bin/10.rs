@@ -1,9 +1,9 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "10";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -19,19 +19,109 @@ const TEST: &str = "\
 10456732
 ";
 
+const TEST_RATING: &str = "\
+.....0.
+..4321.
+..5..2.
+..6543.
+..7..4.
+..8765.
+..9....
+";
+
+const TEST_HIGH: &str = "\
+0123456789abc
+....5.....b..
+....6789a.c..
+";
+
 struct Map {
     map: Vec<Vec<u8>>,
     row_count: usize,
     col_count: usize,
+    max_height: u8,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct Trailhead {
+    row: usize,
+    col: usize,
+    // Number of distinct summits reachable from here.
+    score: usize,
+    // Number of distinct trails from here to any summit.
+    rating: usize,
+    summits: Vec<(usize, usize)>,
+}
+
+// Distinct trails going up from a trailhead, found depth first as they are requested.
+struct Trails<'a> {
+    map: &'a Map,
+    partial: Vec<Vec<(usize, usize)>>,
+}
+
+impl Iterator for Trails<'_> {
+    type Item = Vec<(usize, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(trail) = self.partial.pop() {
+            let (row, col) = *trail.last().unwrap();
+            let height = self.map.map[row][col];
+            if height == self.map.max_height {
+                return Some(trail);
+            }
+
+            for (r, c) in self.map.neighbours(row, col) {
+                if self.map.map[r][c] == height + 1 {
+                    let mut longer = trail.clone();
+                    longer.push((r, c));
+                    self.partial.push(longer);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// Cells that can't be part of a trail.
+const IMPASSABLE: u8 = u8::MAX;
+
 impl Map {
-    fn new() -> Map {
+    fn new(max_height: u8) -> Map {
         Map {
             map: vec![],
             row_count: 0,
             col_count: 0,
+            max_height,
+        }
+    }
+
+    fn parse(input: &str, max_height: u8) -> Result<Map> {
+        let mut map = Map::new(max_height);
+        for line in input.lines() {
+            let row = Map::parse_row(line, max_height)?;
+            ensure!(
+                map.row_count == 0 || row.len() == map.col_count,
+                "Rows have different lengths"
+            );
+            map.add_row(row);
         }
+
+        Ok(map)
+    }
+
+    // Heights go from 0 to 9 and then from a to z, for maps higher than 9. A `.` is impassable.
+    fn parse_row(line: &str, max_height: u8) -> Result<Vec<u8>> {
+        line.chars()
+            .map(|c| match c {
+                '.' => Ok(IMPASSABLE),
+                c => c
+                    .to_digit(36)
+                    .map(|height| height as u8)
+                    .filter(|height| *height <= max_height)
+                    .with_context(|| format!("Invalid height [{}]", c)),
+            })
+            .collect()
     }
 
     fn add_row(&mut self, row: Vec<u8>) {
@@ -43,122 +133,181 @@ impl Map {
         self.map.push(row);
     }
 
-    fn sum_trailhead_scores(&self) -> usize {
-        let mut score = 0;
-        for row in 0..self.row_count {
-            for col in 0..self.col_count {
-                if self.map[row][col] == 0 {
-                    let mut found_endings: Vec<Vec<bool>> =
-                        vec![vec![false; self.col_count]; self.row_count];
-                    self.trailhead_score_from(row, col, &mut found_endings);
-                    score += found_endings
-                        .iter()
-                        .flatten()
-                        .map(|v| if *v { 1 } else { 0 })
-                        .sum::<usize>();
+    fn neighbours(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [
+            (row.wrapping_sub(1), col),
+            (row + 1, col),
+            (row, col.wrapping_sub(1)),
+            (row, col + 1),
+        ]
+        .into_iter()
+        .filter(|(r, c)| *r < self.row_count && *c < self.col_count)
+    }
+
+    // Walks the map once from the summits down to the trailheads. Every cell keeps the set of
+    // summits it reaches, as a bitset, and how many trails go up from it, both built from its
+    // neighbours one step higher.
+    fn trailheads(&self) -> Vec<Trailhead> {
+        let mut by_height = vec![vec![]; self.max_height as usize + 1];
+        for (row, heights) in self.map.iter().enumerate() {
+            for (col, height) in heights.iter().enumerate() {
+                if *height <= self.max_height {
+                    by_height[*height as usize].push((row, col));
                 }
             }
         }
 
-        score
-    }
+        let summits = by_height[self.max_height as usize].clone();
+        let words = summits.len().div_ceil(64);
+        let mut reachable = vec![vec![vec![0u64; words]; self.col_count]; self.row_count];
+        let mut ratings = vec![vec![0usize; self.col_count]; self.row_count];
 
-    fn sum_trailhead_ratings(&self) -> usize {
-        let mut score = 0;
-        for row in 0..self.row_count {
-            for col in 0..self.col_count {
-                if self.map[row][col] == 0 {
-                    let mut found_endings: Vec<Vec<bool>> =
-                        vec![vec![false; self.col_count]; self.row_count];
-                    score += self.trailhead_score_from(row, col, &mut found_endings);
+        for (index, (row, col)) in summits.iter().enumerate() {
+            reachable[*row][*col][index / 64] |= 1 << (index % 64);
+            ratings[*row][*col] = 1;
+        }
+
+        for height in (0..self.max_height).rev() {
+            for &(row, col) in by_height[height as usize].iter() {
+                let mut cell_reachable = vec![0u64; words];
+                let mut cell_rating = 0;
+
+                for (r, c) in self.neighbours(row, col) {
+                    if self.map[r][c] == height + 1 {
+                        cell_reachable
+                            .iter_mut()
+                            .zip(reachable[r][c].iter())
+                            .for_each(|(word, other)| *word |= other);
+                        cell_rating += ratings[r][c];
+                    }
                 }
+
+                reachable[row][col] = cell_reachable;
+                ratings[row][col] = cell_rating;
             }
         }
-        score
+
+        by_height[0]
+            .iter()
+            .map(|&(row, col)| {
+                let summits: Vec<(usize, usize)> = summits
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| reachable[row][col][index / 64] & (1 << (index % 64)) != 0)
+                    .map(|(_, summit)| *summit)
+                    .collect();
+
+                Trailhead {
+                    row,
+                    col,
+                    score: summits.len(),
+                    rating: ratings[row][col],
+                    summits,
+                }
+            })
+            .collect()
     }
-    fn trailhead_score_from(
-        &self,
-        row: usize,
-        col: usize,
-        found_endings: &mut Vec<Vec<bool>>,
-    ) -> usize {
-        let current_height = self.map[row][col];
-        if current_height == 9 {
-            found_endings[row][col] = true;
-            return 1;
-        }
 
-        let next_height = current_height + 1;
+    // Use `take` to stop after the first few trails.
+    fn trails(&self, row: usize, col: usize) -> Trails<'_> {
+        let partial = if self.map[row][col] == 0 {
+            vec![vec![(row, col)]]
+        } else {
+            vec![]
+        };
 
-        let mut result = 0;
+        Trails { map: self, partial }
+    }
 
-        if row > 0 && self.map[row - 1][col] == next_height {
-            result += self.trailhead_score_from(row - 1, col, found_endings);
+    // The height map showing only the cells used by the given trails.
+    fn render_trails(&self, trails: &[Vec<(usize, usize)>]) -> String {
+        let mut grid = vec![vec!['.'; self.col_count]; self.row_count];
+        for &(row, col) in trails.iter().flatten() {
+            grid[row][col] = char::from_digit(self.map[row][col] as u32, 36).unwrap();
         }
 
-        if row + 1 < self.row_count && self.map[row + 1][col] == next_height {
-            result += self.trailhead_score_from(row + 1, col, found_endings);
-        }
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect()
+    }
 
-        if col > 0 && self.map[row][col - 1] == next_height {
-            result += self.trailhead_score_from(row, col - 1, found_endings);
-        }
-        if col + 1 < self.col_count && self.map[row][col + 1] == next_height {
-            result += self.trailhead_score_from(row, col + 1, found_endings);
-        }
+    fn sum_trailhead_scores(&self) -> usize {
+        self.trailheads().iter().map(|t| t.score).sum()
+    }
 
-        result
+    fn sum_trailhead_ratings(&self) -> usize {
+        self.trailheads().iter().map(|t| t.rating).sum()
     }
 }
 
+fn parse(input: &str) -> Result<Map> {
+    Map::parse(input, 9)
+}
+
+fn part1(map: &Map) -> Result<usize> {
+    let result = map.sum_trailhead_scores();
+    Ok(result)
+}
+
+fn part2(map: &Map) -> Result<usize> {
+    let result = map.sum_trailhead_ratings();
+    Ok(result)
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut map = Map::new();
-        for line in reader.lines().map_while(Result::ok) {
-            map.add_row(
-                line.chars()
-                    .map(|c| c.to_digit(10).expect("Digit from 0 to 9") as u8)
-                    .collect(),
-            );
-        }
-
-        let result = map.sum_trailhead_scores();
-        Ok(result)
-    }
-
-    assert_eq!(36, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(36, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut map = Map::new();
-        for line in reader.lines().map_while(Result::ok) {
-            map.add_row(
-                line.chars()
-                    .map(|c| c.to_digit(10).expect("Digit from 0 to 9") as u8)
-                    .collect(),
-            );
-        }
+    assert_eq!(81, part2(&parse(TEST)?)?);
 
-        let result = map.sum_trailhead_ratings();
-        Ok(result)
-    }
+    let map = parse(TEST)?;
+    let trailheads = map.trailheads();
+    assert_eq!(
+        vec![5, 6, 5, 3, 1, 3, 5, 3, 5],
+        trailheads.iter().map(|t| t.score).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![20, 24, 10, 4, 1, 4, 5, 8, 5],
+        trailheads.iter().map(|t| t.rating).collect::<Vec<_>>()
+    );
+    assert_eq!(vec![(6, 4)], trailheads[4].summits);
+    trailheads.iter().for_each(|t| {
+        assert_eq!(t.rating, map.trails(t.row, t.col).count());
+    });
+
+    let map = parse(TEST_RATING)?;
+    let trails: Vec<_> = map.trails(0, 5).collect();
+    assert_eq!(3, trails.len());
+    assert_eq!(2, map.trails(0, 5).take(2).count());
+    assert!(trails.iter().all(|trail| trail.len() == 10));
+    assert_eq!(TEST_RATING, map.render_trails(&trails));
 
-    assert_eq!(81, part2(BufReader::new(TEST.as_bytes()))?);
+    let map = Map::parse(TEST_HIGH, 12)?;
+    assert!(parse(TEST_HIGH).is_err());
+    assert_eq!(2, map.sum_trailhead_scores());
+    assert_eq!(2, map.sum_trailhead_ratings());
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "11";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -13,72 +13,83 @@ const TEST: &str = "\
 125 17
 ";
 
-fn main() -> Result<()> {
-    start_day(DAY);
+// How many stones have each number. Stones with the same number change the same way, so they
+// are kept together.
+fn parse(input: &str) -> Result<HashMap<usize, usize>> {
+    let mut stone_map: HashMap<usize, usize> = HashMap::new();
+    for s in input.split_whitespace() {
+        let stone = s
+            .parse::<usize>()
+            .with_context(|| format!("Invalid stone [{}]", s))?;
+        *stone_map.entry(stone).or_default() += 1;
+    }
 
-    //region Part 1
-    println!("=== Part 1 ===");
+    Ok(stone_map)
+}
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut stone_map: HashMap<usize, usize> = HashMap::new();
-
-        reader
-            .lines()
-            .map_while(Result::ok)
-            .flat_map(|s| {
-                s.split_whitespace()
-                    .map(|s| s.to_owned())
-                    .collect::<Vec<String>>()
-            })
-            .map(|s| s.parse::<usize>())
-            .map_while(Result::ok)
-            .for_each(|s| {
-                match stone_map.get(&s) {
-                    None => stone_map.insert(s, 1),
-                    Some(v) => stone_map.insert(s, v + 1),
-                };
-            });
-
-        for _ in 0..25 {
-            let new_stones: Vec<(usize, usize)> = stone_map
-                .iter()
-                .flat_map(|(k, v)| match k {
-                    0 => vec![(1, *v)],
-                    n if n.to_string().len() % 2 == 0 => {
-                        let str = n.to_string();
-                        let (left, right) = str.split_at(str.len() / 2);
-                        vec![
-                            (left.parse::<usize>().expect("Number"), *v),
-                            (right.parse::<usize>().expect("Number"), *v),
-                        ]
-                    }
-                    n => vec![(n * 2024, *v)],
-                })
-                .collect();
-
-            stone_map = HashMap::new();
-
-            new_stones.iter().for_each(|(k, v)| {
-                match stone_map.get(k) {
-                    None => stone_map.insert(*k, *v),
-                    Some(e) => stone_map.insert(*k, *e + *v),
-                };
-            });
+fn blink(stones: &HashMap<usize, usize>, times: usize) -> Result<usize> {
+    let mut stone_map = stones.clone();
+
+    for _ in 0..times {
+        let mut new_stones: Vec<(usize, usize)> = vec![];
+        for (k, v) in stone_map.iter() {
+            match k {
+                0 => new_stones.push((1, *v)),
+                n if n.to_string().len() % 2 == 0 => {
+                    let str = n.to_string();
+                    let (left, right) = str.split_at(str.len() / 2);
+                    new_stones.push((left.parse::<usize>().expect("Number"), *v));
+                    new_stones.push((right.parse::<usize>().expect("Number"), *v));
+                }
+                n => new_stones.push((
+                    n.checked_mul(2024)
+                        .with_context(|| format!("Stone {} grows too large", n))?,
+                    *v,
+                )),
+            }
         }
 
-        let mut result: usize = 0;
-        stone_map.values().for_each(|c| {
-            result += c;
-        });
+        stone_map = HashMap::new();
+
+        for (k, v) in new_stones {
+            let count = stone_map.entry(k).or_default();
+            *count = count.checked_add(v).context("Too many stones to count")?;
+        }
+    }
+
+    stone_map
+        .values()
+        .try_fold(0usize, |sum, v| sum.checked_add(*v))
+        .context("Too many stones to count")
+}
+
+fn part1(stones: &HashMap<usize, usize>) -> Result<usize> {
+    blink(stones, 25)
+}
+
+fn part2(stones: &HashMap<usize, usize>) -> Result<usize> {
+    blink(stones, 75)
+}
 
-        Ok(result)
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
     }
 
+    //region Part 1
+    println!("=== Part 1 ===");
+
     // Set the expected answer for the test input
-    assert_eq!(55312, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(55312, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     assert_eq!(235850, result);
     //endregion
@@ -86,65 +97,9 @@ fn main() -> Result<()> {
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut stone_map: HashMap<usize, usize> = HashMap::new();
-
-        reader
-            .lines()
-            .map_while(Result::ok)
-            .flat_map(|s| {
-                s.split_whitespace()
-                    .map(|s| s.to_owned())
-                    .collect::<Vec<String>>()
-            })
-            .map(|s| s.parse::<usize>())
-            .map_while(Result::ok)
-            .for_each(|s| {
-                match stone_map.get(&s) {
-                    None => stone_map.insert(s, 1),
-                    Some(v) => stone_map.insert(s, v + 1),
-                };
-            });
-
-        for _ in 0..75 {
-            let new_stones: Vec<(usize, usize)> = stone_map
-                .iter()
-                .flat_map(|(k, v)| match k {
-                    0 => vec![(1, *v)],
-                    n if n.to_string().len() % 2 == 0 => {
-                        let str = n.to_string();
-                        let (left, right) = str.split_at(str.len() / 2);
-                        vec![
-                            (left.parse::<usize>().expect("Number"), *v),
-                            (right.parse::<usize>().expect("Number"), *v),
-                        ]
-                    }
-                    n => vec![(n * 2024, *v)],
-                })
-                .collect();
-
-            stone_map = HashMap::new();
-
-            new_stones.iter().for_each(|(k, v)| {
-                match stone_map.get(k) {
-                    None => stone_map.insert(*k, *v),
-                    Some(e) => stone_map.insert(*k, *e + *v),
-                };
-            });
-        }
-
-        let mut result: usize = 0;
-        stone_map.values().for_each(|c| {
-            result += c;
-        });
-
-        Ok(result)
-    }
-
-    assert_eq!(65601038650482, part2(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(65601038650482, part2(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
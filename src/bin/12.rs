@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "12";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -54,48 +54,63 @@ ABBAAA
 AAAAAA
 ";
 
+fn parse(input: &str) -> Result<Garden> {
+    let width = input.lines().next().map_or(0, |line| line.chars().count());
+    ensure!(
+        input.lines().all(|line| line.chars().count() == width),
+        "Garden rows have different lengths"
+    );
+
+    Ok(Garden::new(input.lines().map(String::from)))
+}
+
+fn part1(garden: &Garden) -> Result<usize> {
+    Ok(garden.clone().compute_with_perimeter())
+}
+
+fn part2(garden: &Garden) -> Result<usize> {
+    Ok(garden.clone().compute_with_sides())
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut garden = Garden::new(reader.lines().map_while(Result::ok));
-        Ok(garden.compute_with_perimeter())
-    }
+    assert_eq!(140, part1(&parse(TEST1)?)?);
+    assert_eq!(772, part1(&parse(TEST2)?)?);
+    assert_eq!(1930, part1(&parse(TEST3)?)?);
 
-    assert_eq!(140, part1(BufReader::new(TEST1.as_bytes()))?);
-    assert_eq!(772, part1(BufReader::new(TEST2.as_bytes()))?);
-    assert_eq!(1930, part1(BufReader::new(TEST3.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut garden = Garden::new(reader.lines().map_while(Result::ok));
-        Ok(garden.compute_with_sides())
-    }
-
-    assert_eq!(80, part2(BufReader::new(TEST1.as_bytes()))?);
-    assert_eq!(436, part2(BufReader::new(TEST2.as_bytes()))?);
-    assert_eq!(236, part2(BufReader::new(TEST4.as_bytes()))?);
-    assert_eq!(368, part2(BufReader::new(TEST5.as_bytes()))?);
+    assert_eq!(80, part2(&parse(TEST1)?)?);
+    assert_eq!(436, part2(&parse(TEST2)?)?);
+    assert_eq!(236, part2(&parse(TEST4)?)?);
+    assert_eq!(368, part2(&parse(TEST5)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
     Ok(())
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Garden {
     map: Vec<Vec<Plot>>,
     row_count: usize,
@@ -105,7 +120,7 @@ struct Garden {
     perimeter_map: HashMap<usize, usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Plot {
     plant_type: char,
     region: Option<usize>,
@@ -266,7 +281,7 @@ impl Garden {
                         .push(Wall::new(col, Side::Top));
                 }
 
-                if row + 1 == self.col_count || self.map[row + 1][col].region != Some(region) {
+                if row + 1 == self.row_count || self.map[row + 1][col].region != Some(region) {
                     horizontal_sides
                         .entry(row + 1)
                         .or_insert(Vec::new())
@@ -289,13 +304,12 @@ impl Garden {
                         last_wall = Some(wall);
                         count += 1;
                     }
-                    Some(c) => {
-                        if c.index + 1 == wall.index && c.side == wall.side {
-                            last_wall = Some(wall);
-                        } else {
-                            last_wall = Some(wall);
-                            count += 1;
-                        }
+                    Some(c) if c.index + 1 == wall.index && c.side == wall.side => {
+                        last_wall = Some(wall);
+                    }
+                    Some(_) => {
+                        last_wall = Some(wall);
+                        count += 1;
                     }
                 }
             }
@@ -1,13 +1,15 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "13";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+// Part 2 prizes are this much further along both axes.
+const PRIZE_OFFSET: i128 = 10000000000000;
 
 const TEST: &str = "\
 Button A: X+94, Y+34
@@ -27,86 +29,95 @@ Button B: X+27, Y+71
 Prize: X=18641, Y=10279
 ";
 
+// How far each button moves the claw along X and Y, and where the prize is.
+#[derive(Debug, Clone)]
+struct ClawMachine {
+    a: (usize, usize),
+    b: (usize, usize),
+    prize: (usize, usize),
+}
+
+fn parse(input: &str) -> Result<Vec<ClawMachine>> {
+    let re_a = Regex::new(r"^Button A\: X\+([0-9]+), Y\+([0-9]+)$")?;
+    let re_b = Regex::new(r"^Button B\: X\+([0-9]+), Y\+([0-9]+)$")?;
+    let re_prize = Regex::new(r"^Prize\: X=([0-9]+), Y=([0-9]+)$")?;
+
+    let capture = |re: &Regex, line: Option<&str>| -> Result<(usize, usize)> {
+        let line = line.context("Claw machine description ends early")?;
+        let captures = re
+            .captures(line)
+            .with_context(|| format!("Expected [{}], got [{}]", re.as_str(), line))?;
+        Ok((captures[1].parse()?, captures[2].parse()?))
+    };
+
+    let mut machines = vec![];
+    let mut lines = input.lines().filter(|line| !line.is_empty());
+    while let Some(line) = lines.next() {
+        machines.push(ClawMachine {
+            a: capture(&re_a, Some(line))?,
+            b: capture(&re_b, lines.next())?,
+            prize: capture(&re_prize, lines.next())?,
+        });
+    }
+
+    Ok(machines)
+}
+
+fn part1(machines: &[ClawMachine]) -> Result<usize> {
+    let result = machines
+        .iter()
+        .map(|m| compute_coins_part1(m.prize.0, m.prize.1, m.a.0, m.a.1, m.b.0, m.b.1))
+        .sum();
+
+    Ok(result)
+}
+
+fn part2(machines: &[ClawMachine]) -> Result<usize> {
+    let mut result: usize = 0;
+    for m in machines {
+        let coins = compute_coins_part2(
+            m.a.0 as i128,
+            m.a.1 as i128,
+            m.b.0 as i128,
+            m.b.1 as i128,
+            m.prize.0 as i128 + PRIZE_OFFSET,
+            m.prize.1 as i128 + PRIZE_OFFSET,
+        )?;
+        result = result
+            .checked_add(coins)
+            .context("Token count is too large")?;
+    }
+
+    Ok(result)
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |machines| part1(machines))?;
+        bench(DAY, 2, parse, |machines| part2(machines))?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result = 0;
-
-        let mut input = reader.lines().map_while(Result::ok);
-        while let Some(line) = input.next() {
-            if line.starts_with("Button A") {
-                let mut re = Regex::new(r"^Button A\: X\+([0-9]+), Y\+([0-9]+)$")?;
-                let mut captures = re.captures(&line).expect("Button A captured.");
-                let a_x = captures[1].parse::<usize>().expect("Number for X");
-                let a_y = captures[2].parse::<usize>().expect("Number for Y");
-
-                let line = input.next().expect("Line for B");
-                re = Regex::new(r"^Button B\: X\+([0-9]+), Y\+([0-9]+)$")?;
-                captures = re.captures(&line).expect("Button B captured.");
-                let b_x = captures[1].parse::<usize>().expect("Number for X");
-                let b_y = captures[2].parse::<usize>().expect("Number for Y");
-
-                let line = input.next().expect("Line for Prize");
-                re = Regex::new(r"^Prize\: X=([0-9]+), Y=([0-9]+)$")?;
-                captures = re.captures(&line).expect("Prize captured.");
-                let target_x = captures[1].parse::<usize>().expect("Number for X");
-                let target_y = captures[2].parse::<usize>().expect("Number for Y");
-
-                result += compute_coins_part1(target_x, target_y, a_x, a_y, b_x, b_y);
-            }
-        }
+    assert_eq!(480, part1(&parse(TEST)?)?);
 
-        Ok(result)
-    }
-
-    assert_eq!(480, part1(BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut result: usize = 0;
-
-        let mut input = reader.lines().map_while(Result::ok);
-        while let Some(line) = input.next() {
-            if line.starts_with("Button A") {
-                let mut re = Regex::new(r"^Button A\: X\+([0-9]+), Y\+([0-9]+)$")?;
-                let mut captures = re.captures(&line).expect("Button A captured.");
-                let a_x = captures[1].parse::<isize>().expect("Number for X");
-                let a_y = captures[2].parse::<isize>().expect("Number for Y");
-
-                let line = input.next().expect("Line for B");
-                re = Regex::new(r"^Button B\: X\+([0-9]+), Y\+([0-9]+)$")?;
-                captures = re.captures(&line).expect("Button B captured.");
-                let b_x = captures[1].parse::<isize>().expect("Number for X");
-                let b_y = captures[2].parse::<isize>().expect("Number for Y");
-
-                let line = input.next().expect("Line for Prize");
-                re = Regex::new(r"^Prize\: X=([0-9]+), Y=([0-9]+)$")?;
-                captures = re.captures(&line).expect("Prize captured.");
-                let target_x = captures[1].parse::<isize>().expect("Number for X") + 10000000000000;
-                let target_y = captures[2].parse::<isize>().expect("Number for Y") + 10000000000000;
-
-                result += compute_coins_part2(a_x, a_y, b_x, b_y, target_x, target_y);
-            }
-        }
-
-        Ok(result)
-    }
-
-    assert_eq!(875318608908, part2(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(875318608908, part2(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
@@ -126,31 +137,182 @@ fn compute_coins_part1(
 
     // Max each button press 100
 
-    for b in 0..100 {
-        for a in 0..100 {
-            if target_x == b * b_x + a * a_x && target_y == b * b_y + a * a_y {
-                return a * 3 + b;
-            }
+    // Parallel buttons can reach the prize in more than one way, so look for the cheapest.
+    let reaches = |a: usize, b: usize, target: usize, a_step: usize, b_step: usize| {
+        a.checked_mul(a_step)
+            .zip(b.checked_mul(b_step))
+            .and_then(|(a, b)| a.checked_add(b))
+            == Some(target)
+    };
+
+    (0..=100)
+        .flat_map(|b| (0..=100).map(move |a| (a, b)))
+        .filter(|(a, b)| reaches(*a, *b, target_x, a_x, b_x) && reaches(*a, *b, target_y, a_y, b_y))
+        .map(|(a, b)| a * 3 + b)
+        .min()
+        .unwrap_or(0)
+}
+
+fn compute_coins_part2(
+    a_x: i128,
+    a_y: i128,
+    b_x: i128,
+    b_y: i128,
+    prize_x: i128,
+    prize_y: i128,
+) -> Result<usize> {
+    let solve = || {
+        let det = a_x.checked_mul(b_y)?.checked_sub(a_y.checked_mul(b_x)?)?;
+        if det == 0 {
+            return Some(None);
+        }
+
+        let a = prize_x
+            .checked_mul(b_y)?
+            .checked_sub(prize_y.checked_mul(b_x)?)?
+            / det;
+        let b = a_x
+            .checked_mul(prize_y)?
+            .checked_sub(a_y.checked_mul(prize_x)?)?
+            / det;
+        Some(Some((a, b)))
+    };
+
+    match solve().context("Claw machine values are too large")? {
+        None => compute_coins_parallel(a_x, a_y, b_x, b_y, prize_x, prize_y),
+        Some((a, b))
+            if a >= 0
+                && b >= 0
+                && reaches(a, b, a_x, b_x, prize_x)
+                && reaches(a, b, a_y, b_y, prize_y) =>
+        {
+            cost(a, b)
         }
+        Some(_) => Ok(0),
     }
+}
+
+fn compute_coins_parallel(
+    a_x: i128,
+    a_y: i128,
+    b_x: i128,
+    b_y: i128,
+    prize_x: i128,
+    prize_y: i128,
+) -> Result<usize> {
+    // An axis that some button moves along, unless neither moves at all.
+    let (u, v, target) = if a_x + b_x > 0 {
+        (a_x, b_x, prize_x)
+    } else if a_y + b_y > 0 {
+        (a_y, b_y, prize_y)
+    } else {
+        return Ok(0);
+    };
+
+    let presses = if u == 0 {
+        (target % v == 0).then(|| (0, target / v))
+    } else if v == 0 {
+        (target % u == 0).then(|| (target / u, 0))
+    } else {
+        cheapest_presses(u, v, target).context("Claw machine values are too large")?
+    };
 
-    0
+    // The other axis only agrees when the prize is on the buttons' line.
+    match presses {
+        Some((a, b)) if reaches(a, b, a_x, b_x, prize_x) && reaches(a, b, a_y, b_y, prize_y) => {
+            cost(a, b)
+        }
+        _ => Ok(0),
+    }
 }
 
-fn compute_coins_part2(
-    a_x: isize,
-    a_y: isize,
-    b_x: isize,
-    b_y: isize,
-    prize_x: isize,
-    prize_y: isize,
-) -> usize {
-    let det = a_x * b_y - a_y * b_x;
-    let a = (prize_x * b_y - prize_y * b_x) / det;
-    let b = (a_x * prize_y - a_y * prize_x) / det;
-    if (a_x * a + b_x * b, a_y * a + b_y * b) == (prize_x, prize_y) {
-        (a * 3 + b) as usize
+// The cheapest `(a, b)` presses with `a * u + b * v == target`, or None when too large to compute.
+// Those presses are `(a0 + k * v / g, b0 - k * u / g)` from any one of them, and each step of k
+// changes the cost by the same amount, so the cheapest is at either end of the valid k.
+fn cheapest_presses(u: i128, v: i128, target: i128) -> Option<Option<(i128, i128)>> {
+    let (g, x, y) = extended_gcd(u, v);
+    if target % g != 0 {
+        return Some(None);
+    }
+
+    let scale = target / g;
+    let (a0, b0) = (x.checked_mul(scale)?, y.checked_mul(scale)?);
+    let (step_a, step_b) = (v / g, u / g);
+    // Presses of A are positive from k_min on, and presses of B up to k_max.
+    let k_min = a0.div_euclid(step_a).checked_neg()?;
+    let k_max = b0.div_euclid(step_b);
+    if k_min > k_max {
+        return Some(None);
+    }
+
+    let k = if 3 * step_a > step_b { k_min } else { k_max };
+    Some(Some((
+        a0.checked_add(k.checked_mul(step_a)?)?,
+        b0.checked_sub(k.checked_mul(step_b)?)?,
+    )))
+}
+
+// Moves too large to compute are past the prize.
+fn reaches(a: i128, b: i128, a_step: i128, b_step: i128, target: i128) -> bool {
+    a.checked_mul(a_step)
+        .zip(b.checked_mul(b_step))
+        .and_then(|(a, b)| a.checked_add(b))
+        == Some(target)
+}
+
+fn cost(a: i128, b: i128) -> Result<usize> {
+    a.checked_mul(3)
+        .and_then(|a| a.checked_add(b))
+        .and_then(|cost| usize::try_from(cost).ok())
+        .context("Token count is too large")
+}
+
+// Returns `(g, x, y)` with `u * x + v * y == g`, the greatest common divisor.
+fn extended_gcd(u: i128, v: i128) -> (i128, i128, i128) {
+    if v == 0 {
+        (u, 1, 0)
     } else {
-        0
+        let (g, x, y) = extended_gcd(v, u % v);
+        (g, y, x - (u / v) * y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Buttons move at least 10 along each axis and prizes are at most 1000 away, so every way to
+    // reach a prize is within the 100 presses per button that the oracle tries. That matters when
+    // the buttons are parallel, since then several ways can reach it and the cheapest counts.
+    fn machine() -> impl Strategy<Value = ClawMachine> {
+        let button = (10..100usize, 10..100usize);
+        let parallel = (10..20usize, 10..20usize, 1..6usize, 1..6usize)
+            .prop_map(|(x, y, a, b)| ((a * x, a * y), (b * x, b * y)));
+        prop_oneof![(button.clone(), button), parallel].prop_flat_map(|(a, b)| {
+            let reachable = (0..=10usize)
+                .prop_flat_map(|presses| (0..=presses).prop_map(move |pa| (pa, presses - pa)))
+                .prop_map(move |(pa, pb)| (pa * a.0 + pb * b.0, pa * a.1 + pb * b.1));
+            let anywhere = (0..=1000usize, 0..=1000usize);
+            prop_oneof![reachable, anywhere].prop_map(move |prize| ClawMachine { a, b, prize })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn solving_matches_trying_every_press(m in machine()) {
+            prop_assert_eq!(
+                compute_coins_part1(m.prize.0, m.prize.1, m.a.0, m.a.1, m.b.0, m.b.1),
+                compute_coins_part2(
+                    m.a.0 as i128,
+                    m.a.1 as i128,
+                    m.b.0 as i128,
+                    m.b.1 as i128,
+                    m.prize.0 as i128,
+                    m.prize.1 as i128,
+                )
+                .unwrap()
+            );
+        }
     }
 }
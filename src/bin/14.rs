@@ -1,10 +1,14 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::render::{write_gif, Frame, ImageFormat};
+use adv_code_2024::terminal::{print_frame, Animation, Cell, Color, Scene};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use regex::Regex;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
 
 const DAY: &str = "14";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -24,163 +28,314 @@ p=2,4 v=2,-3
 p=9,5 v=-3,-3
 ";
 
-fn main() -> Result<()> {
-    start_day(DAY);
-
-    //region Part 1
-    println!("=== Part 1 ===");
-
-    fn part1<R: BufRead>(width: i16, height: i16, reader: R) -> Result<usize> {
-        let mut bathroom = Bathroom::new(width, height);
+fn parse(input: &str) -> Result<Vec<Robot>> {
+    let re = Regex::new(r"^p=(-?[0-9]+),(-?[0-9]+) v=(-?[0-9]+),(-?[0-9]+)$")?;
+
+    input
+        .lines()
+        .map(|line| {
+            let captures = re
+                .captures(line)
+                .with_context(|| format!("Invalid robot [{}]", line))?;
+
+            Ok(Robot {
+                p_x: captures[1].parse()?,
+                p_y: captures[2].parse()?,
+                v_x: captures[3].parse()?,
+                v_y: captures[4].parse()?,
+            })
+        })
+        .collect()
+}
 
-        let re = Regex::new(r"^p=(-?[0-9]+),(-?[0-9]+) v=(-?[0-9]+),(-?[0-9]+)$")?;
+fn part1(width: i64, height: i64, robots: &[Robot]) -> Result<usize> {
+    let bathroom = Bathroom::new(width, height, robots.to_vec());
 
-        reader.lines().map_while(Result::ok).for_each(|l| {
-            let captures = re.captures(&l).expect("Button A captured.");
+    Ok(bathroom.safety_factor(100, Split::centered(width, height)))
+}
 
-            let p_x = captures[1].parse::<i16>().expect("Number for X");
-            let p_y = captures[2].parse::<i16>().expect("Number for Y");
+fn part2(width: i64, height: i64, robots: &[Robot]) -> Result<EasterEgg> {
+    let bathroom = Bathroom::new(width, height, robots.to_vec());
 
-            let v_x = captures[3].parse::<i16>().expect("Number for X");
-            let v_y = captures[4].parse::<i16>().expect("Number for Y");
+    bathroom
+        .find_easter_egg()
+        .context("No moment where the robots cluster")
+}
 
-            bathroom.add_robot(p_x, p_y, v_x, v_y);
-        });
+fn main() -> Result<()> {
+    start_day(DAY);
 
-        bathroom.tick(100);
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |robots| part1(101, 103, robots))?;
+        bench(DAY, 2, parse, |robots| Ok(part2(101, 103, robots)?.time))?;
+        return Ok(());
+    }
 
-        Ok(bathroom.safety_factor())
+    // cargo run --release --bin 14 -- --render out/ --ticks 0..10000 [--image pgm|png]
+    // cargo run --release --bin 14 -- --animate 10 [--ticks 8000..8200]
+    if let Some(options) = RenderOptions::from_args(std::env::args().skip(1))? {
+        let bathroom = Bathroom::new(101, 103, parse(&fs::read_to_string(INPUT_FILE)?)?);
+        return match options.animate {
+            Some(fps) => bathroom.animate(fps, options.ticks),
+            None => bathroom.render(&options),
+        };
     }
 
-    assert_eq!(12, part1(11, 7, BufReader::new(TEST.as_bytes()))?);
+    //region Part 1
+    println!("=== Part 1 ===");
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(101, 103, input_file)?);
-    assert_eq!(231852216, result);
+    assert_eq!(12, part1(11, 7, &parse(TEST)?)?);
+
+    let robot = Robot {
+        p_x: 2,
+        p_y: 4,
+        v_x: 2,
+        v_y: -3,
+    };
+    assert_eq!((1, 3), robot.position_at(5, 11, 7));
+    assert_eq!((1, 3), robot.position_at(5 + 77 * 1_000_000_000_000, 11, 7));
+    assert_eq!(
+        (40_002, 40_004),
+        robot.position_at(20_000, 100_000, 100_000)
+    );
+
+    let test_bathroom = Bathroom::new(10, 6, parse(TEST)?);
+    assert_eq!(
+        [2, 1, 5, 4],
+        test_bathroom.quadrants(100, Split::centered(10, 6))
+    );
+    assert_eq!(
+        [2, 1, 5, 2],
+        test_bathroom.quadrants(100, Split::new(Divider::Cell(5), Divider::Cell(3)))
+    );
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(101, 103, &input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(width: i16, height: i16, reader: R) -> Result<usize> {
-        let mut bathroom = Bathroom::new(width, height);
+    //assert_eq!(0, part2(11, 7, &parse(TEST)?)?);
 
-        let re = Regex::new(r"^p=(-?[0-9]+),(-?[0-9]+) v=(-?[0-9]+),(-?[0-9]+)$")?;
+    let easter_egg = time_snippet!(part2(101, 103, &input)?);
+    Bathroom::new(101, 103, input.clone()).map_dump(easter_egg.time);
+    println!("Confidence = {:.3}", easter_egg.confidence);
+    println!("Result = {}", easter_egg.time);
+    //endregion
 
-        reader.lines().map_while(Result::ok).for_each(|l| {
-            let captures = re.captures(&l).expect("Button A captured.");
+    Ok(())
+}
 
-            let p_x = captures[1].parse::<i16>().expect("Number for X");
-            let p_y = captures[2].parse::<i16>().expect("Number for Y");
+#[derive(Debug)]
+struct Bathroom {
+    width: i64,
+    height: i64,
+    robots: Vec<Robot>,
+}
 
-            let v_x = captures[3].parse::<i16>().expect("Number for X");
-            let v_y = captures[4].parse::<i16>().expect("Number for Y");
+#[derive(Debug, Clone)]
+struct Robot {
+    p_x: i64,
+    p_y: i64,
+    v_x: i64,
+    v_y: i64,
+}
 
-            bathroom.add_robot(p_x, p_y, v_x, v_y);
-        });
+#[derive(Debug)]
+struct RenderOptions {
+    // Images are written there, unless the ticks are animated on the terminal instead.
+    output: Option<PathBuf>,
+    animate: Option<f64>,
+    ticks: Range<u64>,
+    format: ImageFormat,
+}
 
-        let mut tick_count = 0;
-        while !bathroom.is_easter_egg() {
-            tick_count += 1;
-            bathroom.tick(1);
-        }
+// The robots at one moment, `x` where there is one robot and `C` where several meet.
+#[derive(Debug)]
+struct Snapshot {
+    time: u64,
+    map: Vec<Vec<char>>,
+}
 
-        bathroom.map_dump();
+#[derive(Debug)]
+struct EasterEgg {
+    time: u64,
+    confidence: f64,
+}
 
-        Ok(tick_count)
-    }
+// Where a grid is split into quadrants along one axis.
+#[derive(Debug, Clone, Copy)]
+enum Divider {
+    // The given row/column belongs to no quadrant.
+    Cell(i64),
+    // The split falls just before the given row/column, so every cell belongs to a quadrant.
+    Between(i64),
+}
 
-    //assert_eq!(0, part2(11, 7, BufReader::new(TEST.as_bytes()))?);
+#[derive(Debug, Clone, Copy)]
+struct Split {
+    x: Divider,
+    y: Divider,
+}
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(101, 103, input_file)?);
-    println!("Result = {}", result);
-    //endregion
+impl Divider {
+    // Odd sizes have a middle row/column that is left out, as the puzzle asks. Even sizes have
+    // no middle, so the split falls between the two central rows/columns.
+    fn centered(size: i64) -> Divider {
+        if size % 2 == 1 {
+            Divider::Cell(size / 2)
+        } else {
+            Divider::Between(size / 2)
+        }
+    }
 
-    Ok(())
+    // 0 before the split, 1 after it and None on a divider cell.
+    fn side(&self, value: i64) -> Option<usize> {
+        match *self {
+            Divider::Cell(c) if value == c => None,
+            Divider::Cell(c) | Divider::Between(c) if value < c => Some(0),
+            _ => Some(1),
+        }
+    }
 }
 
-#[derive(Debug)]
-struct Bathroom {
-    width: i16,
-    height: i16,
-    robots: Vec<Robot>,
+impl Split {
+    fn new(x: Divider, y: Divider) -> Split {
+        Split { x, y }
+    }
+
+    fn centered(width: i64, height: i64) -> Split {
+        Split::new(Divider::centered(width), Divider::centered(height))
+    }
 }
 
-#[derive(Debug)]
-struct Robot {
-    p_x: i16,
-    p_y: i16,
-    v_x: i16,
-    v_y: i16,
+impl RenderOptions {
+    // Reads `--render`, `--animate`, `--ticks` and `--image`, leaving the other arguments, like
+    // `--bench`, to the rest of the day.
+    fn from_args(args: impl Iterator<Item = String>) -> Result<Option<RenderOptions>> {
+        let args: Vec<String> = args.collect();
+        let value = |name: &str| -> Result<Option<&str>> {
+            match args.iter().position(|arg| arg == name) {
+                None => Ok(None),
+                Some(position) => args
+                    .get(position + 1)
+                    .map(|value| Some(value.as_str()))
+                    .with_context(|| format!("Missing value for {}", name)),
+            }
+        };
+
+        let output = value("--render")?.map(PathBuf::from);
+        let animate = value("--animate")?
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|fps| *fps > 0.0)
+                    .with_context(|| format!("Expected frames per second, got {}", value))
+            })
+            .transpose()?;
+        let ticks = value("--ticks")?
+            .map(|value| -> Result<Range<u64>> {
+                let (start, end) = value
+                    .split_once("..")
+                    .with_context(|| format!("Expected a range like 0..100, got {}", value))?;
+                let ticks = start.parse()?..end.parse()?;
+                ensure!(
+                    ticks.start < ticks.end,
+                    "Expected a range that ends after it starts, got {}",
+                    value
+                );
+                Ok(ticks)
+            })
+            .transpose()?;
+        let format = value("--image")?
+            .map(ImageFormat::parse)
+            .transpose()?
+            .unwrap_or(ImageFormat::Png);
+
+        if output.is_none() && animate.is_none() {
+            return Ok(None);
+        }
+
+        // Robots move forever, so an animation goes on until it is quit.
+        let ticks = ticks.unwrap_or(match animate {
+            Some(_) => 0..u64::MAX,
+            None => 0..1,
+        });
+
+        Ok(Some(RenderOptions {
+            output,
+            animate,
+            ticks,
+            format,
+        }))
+    }
 }
 
 impl Bathroom {
-    fn new(width: i16, height: i16) -> Bathroom {
+    fn new(width: i64, height: i64, robots: Vec<Robot>) -> Bathroom {
         Bathroom {
             width,
             height,
-            robots: vec![],
+            robots,
         }
     }
 
-    fn add_robot(&mut self, p_x: i16, p_y: i16, v_x: i16, v_y: i16) {
-        self.robots.push(Robot { p_x, p_y, v_x, v_y });
+    fn positions_at(&self, time: u64) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.robots
+            .iter()
+            .map(move |robot| robot.position_at(time, self.width, self.height))
     }
 
-    fn tick(&mut self, count: usize) {
-        for _ in 0..count {
-            for robot in self.robots.iter_mut() {
-                robot.tick(self.width, self.height);
+    // Robot count per quadrant: top left, top right, bottom left and bottom right.
+    fn quadrants(&self, time: u64, split: Split) -> [usize; 4] {
+        let mut quadrants = [0; 4];
+
+        for (x, y) in self.positions_at(time) {
+            if let (Some(col), Some(row)) = (split.x.side(x), split.y.side(y)) {
+                quadrants[row * 2 + col] += 1;
             }
         }
+
+        quadrants
     }
 
-    fn safety_factor(&self) -> usize {
-        let mid_width = self.width / 2;
-        let mid_height = self.height / 2;
+    fn safety_factor(&self, time: u64, split: Split) -> usize {
+        self.quadrants(time, split).iter().product()
+    }
 
-        let mut q1: usize = 0;
-        let mut q2: usize = 0;
-        let mut q3: usize = 0;
-        let mut q4: usize = 0;
+    fn map_dump(&self, time: u64) {
+        print_frame(&self.snapshot(time));
+    }
 
-        for robot in self.robots.iter() {
-            if robot.p_y < mid_height {
-                if robot.p_x < mid_width {
-                    q1 += 1;
-                }
-                if robot.p_x > mid_width {
-                    q2 += 1;
-                }
-            }
-            if robot.p_y > mid_height {
-                if robot.p_x < mid_width {
-                    q3 += 1;
-                }
-                if robot.p_x > mid_width {
-                    q4 += 1;
-                }
-            }
+    fn snapshot(&self, time: u64) -> Snapshot {
+        Snapshot {
+            time,
+            map: self.robots_to_map(time),
         }
-
-        q1 * q2 * q3 * q4
     }
 
-    fn map_dump(&self) {
-        self.robots_to_map().iter().for_each(|row| {
-            println!("{}", row.iter().collect::<String>());
-        });
-        println!();
+    fn animate(&self, fps: f64, ticks: Range<u64>) -> Result<()> {
+        let mut snapshot = self.snapshot(ticks.start);
+        Animation::new(fps).run(&mut snapshot, |snapshot| {
+            if snapshot.time + 1 >= ticks.end {
+                return false;
+            }
+            *snapshot = self.snapshot(snapshot.time + 1);
+            true
+        })
     }
 
-    fn robots_to_map(&self) -> Vec<Vec<char>> {
+    fn robots_to_map(&self, time: u64) -> Vec<Vec<char>> {
         let mut map = vec![vec![' '; self.width as usize]; self.height as usize];
 
-        for robot in self.robots.iter() {
-            let x = robot.p_x as usize;
-            let y = robot.p_y as usize;
+        for (x, y) in self.positions_at(time) {
+            let x = x as usize;
+            let y = y as usize;
             if map[y][x] == 'x' {
                 map[y][x] = 'C';
             } else {
@@ -190,29 +345,150 @@ impl Bathroom {
         map
     }
 
-    fn is_easter_egg(&self) -> bool {
-        let rows = self.robots_to_map();
-        for row in rows {
-            let row_str = row.iter().collect::<String>();
-            if row_str.contains("xxxxxxxxxxxxxxx") {
-                return true;
-            }
+    fn density_frame(&self, time: u64) -> Frame {
+        Frame::from_density(
+            self.width as usize,
+            self.height as usize,
+            self.positions_at(time)
+                .map(|(x, y)| (x as usize, y as usize)),
+        )
+    }
+
+    // Writes one image per tick plus an animation over the whole range.
+    fn render(&self, options: &RenderOptions) -> Result<()> {
+        let output = options
+            .output
+            .as_ref()
+            .context("Missing --render directory")?;
+        std::fs::create_dir_all(output)?;
+
+        for time in options.ticks.clone() {
+            let path = output.join(format!("{:06}.{}", time, options.format.extension()));
+            self.density_frame(time).write(&path, options.format)?;
         }
-        false
+
+        write_gif(
+            &output.join("ticks.gif"),
+            self.width as usize,
+            self.height as usize,
+            100,
+            options.ticks.clone().map(|time| self.density_frame(time)),
+        )?;
+
+        println!(
+            "Rendered ticks {}..{} to {}",
+            options.ticks.start,
+            options.ticks.end,
+            output.display()
+        );
+
+        Ok(())
+    }
+
+    // The picture is the moment robots are most clustered. X positions repeat every `width`
+    // ticks and Y positions every `height` ticks, so each axis is searched over its own period
+    // and both times are combined with the chinese remainder theorem.
+    fn find_easter_egg(&self) -> Option<EasterEgg> {
+        let x_variances: Vec<f64> = (0..self.width as u64)
+            .map(|t| self.variance(self.positions_at(t).map(|(x, _)| x)))
+            .collect();
+        let y_variances: Vec<f64> = (0..self.height as u64)
+            .map(|t| self.variance(self.positions_at(t).map(|(_, y)| y)))
+            .collect();
+
+        let (x_time, x_confidence) = lowest_variance(&x_variances)?;
+        let (y_time, y_confidence) = lowest_variance(&y_variances)?;
+
+        Some(EasterEgg {
+            time: chinese_remainder(x_time, self.width as u64, y_time, self.height as u64)?,
+            confidence: x_confidence.min(y_confidence),
+        })
+    }
+
+    fn variance(&self, values: impl Iterator<Item = i64>) -> f64 {
+        let values: Vec<f64> = values.map(|v| v as f64).collect();
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count
     }
 }
 
 impl Robot {
-    fn tick(&mut self, width: i16, height: i16) {
-        self.p_x += self.v_x;
-        self.p_y += self.v_y;
-        self.p_x %= width;
-        self.p_y %= height;
-        if self.p_x < 0 {
-            self.p_x += width;
-        }
-        if self.p_y < 0 {
-            self.p_y += height;
+    // Position after `time` seconds. Wraps around the walls with modular arithmetic, so any time
+    // can be computed directly without stepping through the ones before it.
+    fn position_at(&self, time: u64, width: i64, height: i64) -> (i64, i64) {
+        (
+            wrap(self.p_x, self.v_x, time, width),
+            wrap(self.p_y, self.v_y, time, height),
+        )
+    }
+}
+
+impl Scene for Snapshot {
+    fn width(&self) -> usize {
+        self.map.first().map_or(0, Vec::len)
+    }
+
+    fn height(&self) -> usize {
+        self.map.len()
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        match self.map[y][x] {
+            'x' => Cell::new('x', Color::Green),
+            'C' => Cell::new('C', Color::Yellow),
+            c => Cell::new(c, Color::DarkGrey),
         }
     }
+
+    fn status(&self) -> String {
+        format!("Second {}", self.time)
+    }
+}
+
+fn wrap(position: i64, velocity: i64, time: u64, size: i64) -> i64 {
+    let size = size as i128;
+    let steps = (time as i128 % size) * (velocity as i128 % size);
+
+    (position as i128 + steps).rem_euclid(size) as i64
+}
+
+// Returns the index of the lowest variance and how far it stands out from the average, from 0
+// (indistinguishable) to 1 (all robots on a single line).
+fn lowest_variance(variances: &[f64]) -> Option<(u64, f64)> {
+    let (index, lowest) = variances
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    let mean = variances.iter().sum::<f64>() / variances.len() as f64;
+
+    if mean == 0.0 {
+        return Some((index as u64, 0.0));
+    }
+
+    Some((index as u64, 1.0 - lowest / mean))
+}
+
+// Smallest t such that t = a (mod m) and t = b (mod n).
+fn chinese_remainder(a: u64, m: u64, b: u64, n: u64) -> Option<u64> {
+    let (gcd, p, _) = extended_gcd(m as i128, n as i128);
+    let diff = b as i128 - a as i128;
+    if diff % gcd != 0 {
+        return None;
+    }
+
+    let lcm = m as i128 / gcd * n as i128;
+    let step = (diff / gcd * p).rem_euclid(n as i128 / gcd);
+
+    Some((a as i128 + m as i128 * step).rem_euclid(lcm) as u64)
+}
+
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+
+    let (gcd, x, y) = extended_gcd(b, a % b);
+    (gcd, y, x - (a / b) * y)
 }
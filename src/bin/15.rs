@@ -1,9 +1,11 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::terminal::{fps_from_args, Animation, Cell, Color, Scene};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::VecDeque;
+use std::fs;
 
 const DAY: &str = "15";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -57,487 +59,523 @@ const TEST_3: &str = "\
 <vv<<^^<<^^
 ";
 
+const TEST_WIDE_BOXES: &str = "\
+##########
+#@[--]...#
+#..[]....#
+##########
+
+>>>>
+";
+
+const TEST_WIDE_BOXES_UP: &str = "\
+#######
+#.....#
+#.[-].#
+#..@..#
+#######
+
+^^
+";
+
+fn part1(puzzle: &Puzzle) -> Result<usize> {
+    simulate(&puzzle.narrow, &puzzle.moves)
+}
+
+fn part2(puzzle: &Puzzle) -> Result<usize> {
+    simulate(&puzzle.wide, &puzzle.moves)
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
-    //region Part 1
-    println!("=== Part 1 ===");
-
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let mut wharehouse = Wharehouse::new(&mut lines);
-
-        for line in lines.by_ref() {
-            line.chars().for_each(|c| {
-                //println!("Move: {}", c);
-                match c {
-                    '<' => wharehouse.move_left(),
-                    '>' => wharehouse.move_right(),
-                    '^' => wharehouse.move_up(),
-                    'v' => wharehouse.move_down(),
-                    c => panic!("Unexpected motion [{}]!", c),
-                };
-                //wharehouse.dump_state();
-            });
-        }
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
 
-        Ok(wharehouse.sum_gps())
+    // Plays part 2 in the wide warehouse.
+    // cargo run --release --bin 15 -- --animate 60
+    if let Some(fps) = fps_from_args(&std::env::args().skip(1).collect::<Vec<_>>())? {
+        let Puzzle {
+            wide: mut wharehouse,
+            moves,
+            ..
+        } = parse(&fs::read_to_string(INPUT_FILE)?)?;
+        let mut moves = moves.into_iter();
+        return Animation::new(fps).run(&mut wharehouse, |wharehouse| {
+            moves
+                .next()
+                .map(|direction| wharehouse.push(direction))
+                .is_some()
+        });
     }
 
-    assert_eq!(2028, part1(BufReader::new(TEST_1.as_bytes()))?);
-    assert_eq!(10092, part1(BufReader::new(TEST_2.as_bytes()))?);
+    //region Part 1
+    println!("=== Part 1 ===");
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    assert_eq!(2028, part1(&parse(TEST_1)?)?);
+    assert_eq!(10092, part1(&parse(TEST_2)?)?);
+    assert_eq!(105 + 203, part1(&parse(TEST_WIDE_BOXES)?)?);
+    assert_eq!(102, part1(&parse(TEST_WIDE_BOXES_UP)?)?);
+
+    let Puzzle {
+        narrow: mut wharehouse,
+        moves,
+        ..
+    } = parse(TEST_1)?;
+    let start_gps = wharehouse.sum_gps();
+    moves.iter().for_each(|direction| {
+        wharehouse.push(*direction);
+    });
+    assert_eq!(2028, wharehouse.sum_gps());
+    assert_eq!(moves.len(), wharehouse.undo(moves.len() + 1));
+    assert_eq!(start_gps, wharehouse.sum_gps());
+    assert!(wharehouse.redo());
+    wharehouse.jump_to(moves.len());
+    assert!(!wharehouse.redo());
+    assert_eq!(2028, wharehouse.sum_gps());
+    // The box starting at 5,1 is first pushed by the fifth move.
+    assert_eq!(Some(5), wharehouse.bisect(|w| w.boxes[1].x == 5));
+    assert_eq!(5, wharehouse.cursor);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let mut wharehouse = WharehouseWide::new(&mut lines);
-
-        // wharehouse.dump_state();
-        for line in lines.by_ref() {
-            line.chars().for_each(|c| {
-                // println!("Move: {}", c);
-                match c {
-                    '<' => wharehouse.move_left(),
-                    '>' => wharehouse.move_right(),
-                    '^' => wharehouse.move_up(),
-                    'v' => wharehouse.move_down(),
-                    c => panic!("Unexpected motion [{}]!", c),
-                };
-                // wharehouse.dump_state();
-                wharehouse.validate_state();
-            });
-        }
-
-        Ok(wharehouse.sum_gps())
-    }
+    assert_eq!(1751, part2(&parse(TEST_1)?)?);
+    assert_eq!(9021, part2(&parse(TEST_2)?)?);
+    assert_eq!(618, part2(&parse(TEST_3)?)?);
+    assert_eq!(107 + 206, part2(&parse(TEST_WIDE_BOXES)?)?);
 
-    assert_eq!(1751, part2(BufReader::new(TEST_1.as_bytes()))?);
-    assert_eq!(9021, part2(BufReader::new(TEST_2.as_bytes()))?);
-    assert_eq!(618, part2(BufReader::new(TEST_3.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
     Ok(())
 }
 
-#[derive(Debug)]
-struct Wharehouse {
-    map: Vec<Vec<Tile>>,
-    robot_x: usize,
-    robot_y: usize,
+// The warehouse as given, its twice as wide version, and the robot's moves.
+struct Puzzle {
+    narrow: Wharehouse,
+    wide: Wharehouse,
+    moves: Vec<Direction>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Tile {
-    Wall, // #
-    Free, // .
-    Box,  // O
+fn parse(input: &str) -> Result<Puzzle> {
+    let map: Vec<String> = input
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+
+    Ok(Puzzle {
+        narrow: Wharehouse::new(&mut map.iter().cloned(), 1)?,
+        wide: Wharehouse::new(&mut map.iter().cloned(), 2)?,
+        moves: parse_moves(input.lines().skip(map.len()).map(String::from))?,
+    })
 }
 
-impl Wharehouse {
-    fn new(lines: &mut impl Iterator<Item = String>) -> Self {
-        let mut map = vec![];
-        let mut robot_x = None;
-        let mut robot_y = None;
+fn simulate(wharehouse: &Wharehouse, moves: &[Direction]) -> Result<usize> {
+    let mut wharehouse = wharehouse.clone();
 
-        for (y, line) in lines.by_ref().enumerate() {
-            if line.is_empty() {
-                break;
-            }
-
-            map.push(
-                line.chars()
-                    .enumerate()
-                    .map(|(x, c)| match c {
-                        '#' => Tile::Wall,
-                        '.' => Tile::Free,
-                        'O' => Tile::Box,
-                        '@' => {
-                            robot_x = Some(x);
-                            robot_y = Some(y);
-                            Tile::Free
-                        }
-                        _ => panic!("Unexpected tile"),
-                    })
-                    .collect(),
-            );
-        }
-
-        Self {
-            map,
-            robot_x: robot_x.expect("Robot X"),
-            robot_y: robot_y.expect("Rboot Y"),
-        }
+    for direction in moves {
+        wharehouse.push(*direction);
     }
+    wharehouse.validate_state()?;
 
-    fn move_left(&mut self) {
-        if self.attemp_left(self.robot_x - 1, self.robot_y) {
-            self.robot_x -= 1;
-        }
-    }
+    Ok(wharehouse.sum_gps())
+}
 
-    fn attemp_left(&mut self, target_x: usize, target_y: usize) -> bool {
-        match self.get_at(target_x, target_y) {
-            Tile::Free => true,
-            Tile::Wall => false,
-            Tile::Box if self.attemp_left(target_x - 1, target_y) => {
-                self.map[target_y][target_x - 1] = Tile::Box;
-                self.map[target_y][target_x] = Tile::Free;
-                true
-            }
-            _ => false,
-        }
+impl Scene for Wharehouse {
+    fn width(&self) -> usize {
+        self.map.iter().map(Vec::len).max().unwrap_or(0)
     }
 
-    fn get_at(&self, target_x: usize, target_y: usize) -> Tile {
-        self.map[target_y][target_x]
+    fn height(&self) -> usize {
+        self.map.len()
     }
 
-    fn move_right(&mut self) {
-        if self.attemp_right(self.robot_x + 1, self.robot_y) {
-            self.robot_x += 1;
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        if (x, y) == (self.robot_x, self.robot_y) {
+            return Cell::new('@', Color::Red);
         }
-    }
 
-    fn attemp_right(&mut self, target_x: usize, target_y: usize) -> bool {
-        match self.get_at(target_x, target_y) {
-            Tile::Free => true,
-            Tile::Wall => false,
-            Tile::Box if self.attemp_right(target_x + 1, target_y) => {
-                self.map[target_y][target_x + 1] = Tile::Box;
-                self.map[target_y][target_x] = Tile::Free;
-                true
+        // Rows can be shorter than the widest one.
+        let Some(tile) = self.map[y].get(x) else {
+            return Cell::new(' ', Color::Reset);
+        };
+
+        match *tile {
+            Tile::Box(id) => {
+                let b = self.boxes[id];
+                let glyph = match x - b.x {
+                    _ if b.width == 1 => 'O',
+                    0 => '[',
+                    i if i + 1 == b.width => ']',
+                    _ => '-',
+                };
+                Cell::new(glyph, Color::DarkYellow)
             }
-            _ => false,
+            Tile::Free => Cell::new('.', Color::DarkGrey),
+            Tile::Wall => Cell::new('#', Color::Grey),
         }
     }
 
-    fn move_up(&mut self) {
-        if self.attemp_up(self.robot_x, self.robot_y - 1) {
-            self.robot_y -= 1;
-        }
+    fn status(&self) -> String {
+        format!("Move {}, GPS sum {}", self.cursor, self.sum_gps())
     }
 
-    fn attemp_up(&mut self, target_x: usize, target_y: usize) -> bool {
-        match self.get_at(target_x, target_y) {
-            Tile::Free => true,
-            Tile::Wall => false,
-            Tile::Box if self.attemp_up(target_x, target_y - 1) => {
-                self.map[target_y - 1][target_x] = Tile::Box;
-                self.map[target_y][target_x] = Tile::Free;
-                true
-            }
-            _ => false,
+    fn focus(&self) -> Option<(usize, usize)> {
+        Some((self.robot_x, self.robot_y))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,    // ^
+    Down,  // v
+    Left,  // <
+    Right, // >
+}
+
+impl Direction {
+    fn parse(c: char) -> Result<Direction> {
+        match c {
+            '^' => Ok(Direction::Up),
+            'v' => Ok(Direction::Down),
+            '<' => Ok(Direction::Left),
+            '>' => Ok(Direction::Right),
+            c => bail!("Unexpected motion [{}]!", c),
         }
     }
 
-    fn move_down(&mut self) {
-        if self.attemp_down(self.robot_x, self.robot_y + 1) {
-            self.robot_y += 1;
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
         }
     }
 
-    fn attemp_down(&mut self, target_x: usize, target_y: usize) -> bool {
-        match self.get_at(target_x, target_y) {
-            Tile::Free => true,
-            Tile::Wall => false,
-            Tile::Box if self.attemp_down(target_x, target_y + 1) => {
-                self.map[target_y + 1][target_x] = Tile::Box;
-                self.map[target_y][target_x] = Tile::Free;
-                true
-            }
-            _ => false,
+    fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
         }
     }
+}
 
-    fn sum_gps(&self) -> usize {
-        let mut result = 0;
+fn parse_moves(lines: impl Iterator<Item = String>) -> Result<Vec<Direction>> {
+    lines
+        .flat_map(|line| line.chars().collect::<Vec<_>>())
+        .map(Direction::parse)
+        .collect()
+}
 
-        self.map.iter().enumerate().for_each(|(row_index, row)| {
-            row.iter().enumerate().for_each(|(col_index, tile)| {
-                if *tile == Tile::Box {
-                    result += row_index * 100 + col_index;
-                }
-            });
-        });
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,       // #
+    Free,       // .
+    Box(usize), // O or [..], index into the boxes
+}
 
-        result
-    }
+// A box spanning `width` cells to the right of (x, y).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StoredBox {
+    x: usize,
+    y: usize,
+    width: usize,
+}
 
-    fn dump_state(&self) {
-        self.map.iter().enumerate().for_each(|(r_index, r)| {
-            println!(
-                "{}",
-                r.iter()
-                    .enumerate()
-                    .map(|(t_index, t)| {
-                        if r_index == self.robot_y && t_index == self.robot_x {
-                            return '@';
-                        }
-                        match t {
-                            Tile::Box => 'O',
-                            Tile::Free => '.',
-                            Tile::Wall => '#',
-                        }
-                    })
-                    .collect::<String>()
-            );
-        });
-    }
+// Everything needed to replay a move in either direction: the robot positions before and after
+// it and the boxes that were pushed one cell towards `direction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MoveDelta {
+    direction: Direction,
+    robot_from: (usize, usize),
+    robot_to: (usize, usize),
+    displaced: Vec<usize>,
 }
 
-#[derive(Debug)]
-struct WharehouseWide {
-    map: Vec<Vec<TileWide>>,
+#[derive(Debug, Clone)]
+struct Wharehouse {
+    map: Vec<Vec<Tile>>,
+    boxes: Vec<StoredBox>,
     robot_x: usize,
     robot_y: usize,
+    history: Vec<MoveDelta>,
+    // Number of moves from `history` currently applied. Moves after it can be redone.
+    cursor: usize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum TileWide {
-    Wall,     // #
-    Free,     // .
-    BoxLeft,  // [
-    BoxRight, // ]
+impl StoredBox {
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.x..self.x + self.width).map(|x| (x, self.y))
+    }
 }
 
-impl WharehouseWide {
-    fn new(lines: &mut impl Iterator<Item = String>) -> Self {
+impl Wharehouse {
+    // Reads the map up to the first empty line. Each cell is repeated `scale` times, so an `O`
+    // becomes a box `scale` cells wide. Boxes can also be drawn directly as `[]`, `[-]`, `[--]`...,
+    // and their cells are repeated the same way.
+    fn new(lines: &mut impl Iterator<Item = String>, scale: usize) -> Result<Self> {
         let mut map = vec![];
-        let mut robot_x = None;
-        let mut robot_y = None;
+        let mut boxes = vec![];
+        let mut robot = None;
 
         for (y, line) in lines.by_ref().enumerate() {
             if line.is_empty() {
                 break;
             }
 
-            map.push(
-                line.chars()
-                    .flat_map(|c| {
-                        match c {
-                            '#' => "##",
-                            'O' => "[]",
-                            '.' => "..",
-                            '@' => "@.",
-                            _ => panic!("Unexpected input to double."),
-                        }
-                        .chars()
-                    })
-                    .enumerate()
-                    .map(|(x, c)| match c {
-                        '#' => TileWide::Wall,
-                        '.' => TileWide::Free,
-                        '[' => TileWide::BoxLeft,
-                        ']' => TileWide::BoxRight,
-                        '@' => {
-                            robot_x = Some(x);
-                            robot_y = Some(y);
-                            TileWide::Free
-                        }
-                        _ => panic!("Unexpected tile"),
-                    })
-                    .collect(),
-            );
+            let mut row = vec![];
+            let mut open_box = None;
+            for c in line.chars() {
+                let x = row.len();
+                let tile = match c {
+                    '#' => Tile::Wall,
+                    '.' => Tile::Free,
+                    '@' => {
+                        robot = Some((x, y));
+                        Tile::Free
+                    }
+                    'O' => {
+                        boxes.push(StoredBox { x, y, width: scale });
+                        Tile::Box(boxes.len() - 1)
+                    }
+                    '[' if open_box.is_none() => {
+                        open_box = Some(x);
+                        Tile::Box(boxes.len())
+                    }
+                    '-' if open_box.is_some() => Tile::Box(boxes.len()),
+                    ']' if open_box.is_some() => {
+                        let start = open_box.take().unwrap();
+                        boxes.push(StoredBox {
+                            x: start,
+                            y,
+                            width: x + scale - start,
+                        });
+                        Tile::Box(boxes.len() - 1)
+                    }
+                    c => bail!("Unexpected tile [{}] at line {}", c, y + 1),
+                };
+                row.extend(std::iter::repeat_n(tile, scale));
+            }
+            ensure!(open_box.is_none(), "Unclosed box at line {}", y + 1);
+
+            map.push(row);
         }
 
-        Self {
+        let (robot_x, robot_y) = robot.context("Robot not found")?;
+
+        Ok(Self {
             map,
-            robot_x: robot_x.expect("Robot X"),
-            robot_y: robot_y.expect("Rboot Y"),
-        }
+            boxes,
+            robot_x,
+            robot_y,
+            history: vec![],
+            cursor: 0,
+        })
     }
 
-    fn move_left(&mut self) {
-        if self.attemp_left(self.robot_y, self.robot_x - 1) {
-            self.robot_x -= 1;
-        }
+    fn get_at(&self, x: usize, y: usize) -> Tile {
+        self.map[y][x]
     }
 
-    fn attemp_left(&mut self, target_y: usize, target_x: usize) -> bool {
-        match self.get_at(target_y, target_x) {
-            TileWide::Free => true,
-            TileWide::Wall => false,
-            t if self.attemp_left(target_y, target_x - 1) => {
-                self.map[target_y][target_x - 1] = t;
-                self.map[target_y][target_x] = TileWide::Free;
-                true
-            }
-            _ => false,
-        }
-    }
+    fn step(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.delta();
+        let x = x.checked_add_signed(dx)?;
+        let y = y.checked_add_signed(dy)?;
 
-    fn get_at(&self, target_y: usize, target_x: usize) -> TileWide {
-        self.map[target_y][target_x]
+        (y < self.map.len() && x < self.map[y].len()).then_some((x, y))
     }
 
-    fn move_right(&mut self) {
-        if self.attemp_right(self.robot_y, self.robot_x + 1) {
-            self.robot_x += 1;
-        }
+    // Moves the robot one cell, pushing every box in the way. The push front is explored breadth
+    // first: each box found adds the cells in front of it, until either a wall blocks the move or
+    // only free cells are left. Blocked moves are recorded too, so history entries line up with
+    // the move list.
+    fn push(&mut self, direction: Direction) -> bool {
+        let robot_from = (self.robot_x, self.robot_y);
+        let resolved = self
+            .step(robot_from, direction)
+            .and_then(|target| Some((target, self.push_front(target, direction)?)));
+
+        let (robot_to, displaced) = resolved.unwrap_or((robot_from, vec![]));
+        let delta = MoveDelta {
+            direction,
+            robot_from,
+            robot_to,
+            displaced,
+        };
+
+        self.apply(&delta, direction);
+        self.history.truncate(self.cursor);
+        self.history.push(delta);
+        self.cursor += 1;
+
+        robot_from != robot_to
     }
 
-    fn attemp_right(&mut self, target_y: usize, target_x: usize) -> bool {
-        match self.get_at(target_y, target_x) {
-            TileWide::Free => true,
-            TileWide::Wall => false,
-            t if self.attemp_right(target_y, target_x + 1) => {
-                self.map[target_y][target_x + 1] = t;
-                self.map[target_y][target_x] = TileWide::Free;
-                true
+    // Boxes that move when pushing into `target`, or None if a wall is in the way.
+    fn push_front(&self, target: (usize, usize), direction: Direction) -> Option<Vec<usize>> {
+        let mut moving = vec![];
+        let mut seen = vec![false; self.boxes.len()];
+        let mut front = VecDeque::from([target]);
+
+        while let Some((x, y)) = front.pop_front() {
+            match self.get_at(x, y) {
+                Tile::Free => (),
+                Tile::Wall => return None,
+                Tile::Box(id) if !seen[id] => {
+                    seen[id] = true;
+                    moving.push(id);
+                    for cell in self.boxes[id].cells() {
+                        front.push_back(self.step(cell, direction)?);
+                    }
+                }
+                Tile::Box(_) => (),
             }
-            _ => false,
         }
-    }
 
-    fn move_up(&mut self) {
-        if self.can_move_up(self.robot_y - 1, self.robot_x) {
-            self.do_move_up(self.robot_y - 1, self.robot_x);
-            self.robot_y -= 1;
-        }
+        Some(moving)
     }
 
-    fn can_move_up(&self, target_y: usize, target_x: usize) -> bool {
-        match self.get_at(target_y, target_x) {
-            TileWide::Free => true,
-            TileWide::Wall => false,
-            TileWide::BoxLeft => {
-                self.can_move_up(target_y - 1, target_x)
-                    && self.can_move_up(target_y - 1, target_x + 1)
+    // Shifts the displaced boxes one cell towards `direction`, which is the move's own direction
+    // when applying it and the opposite one when reverting it.
+    fn apply(&mut self, delta: &MoveDelta, direction: Direction) {
+        for &id in delta.displaced.iter() {
+            for (x, y) in self.boxes[id].cells() {
+                self.map[y][x] = Tile::Free;
             }
-            TileWide::BoxRight => {
-                self.can_move_up(target_y - 1, target_x)
-                    && self.can_move_up(target_y - 1, target_x - 1)
+        }
+        for &id in delta.displaced.iter() {
+            let (x, y) = self
+                .step((self.boxes[id].x, self.boxes[id].y), direction)
+                .expect("Box stays in the map");
+            self.boxes[id].x = x;
+            self.boxes[id].y = y;
+            for (x, y) in self.boxes[id].cells() {
+                self.map[y][x] = Tile::Box(id);
             }
         }
+
+        (self.robot_x, self.robot_y) = if direction == delta.direction {
+            delta.robot_to
+        } else {
+            delta.robot_from
+        };
     }
 
-    fn do_move_up(&mut self, target_y: usize, target_x: usize) {
-        match self.get_at(target_y, target_x) {
-            TileWide::BoxLeft => {
-                self.do_move_up(target_y - 1, target_x);
-                self.do_move_up(target_y - 1, target_x + 1);
-                self.map[target_y - 1][target_x] = TileWide::BoxLeft;
-                self.map[target_y - 1][target_x + 1] = TileWide::BoxRight;
-                self.map[target_y][target_x] = TileWide::Free;
-                self.map[target_y][target_x + 1] = TileWide::Free;
-            }
-            TileWide::BoxRight => {
-                self.do_move_up(target_y - 1, target_x);
-                self.do_move_up(target_y - 1, target_x - 1);
-                self.map[target_y - 1][target_x - 1] = TileWide::BoxLeft;
-                self.map[target_y - 1][target_x] = TileWide::BoxRight;
-                self.map[target_y][target_x] = TileWide::Free;
-                self.map[target_y][target_x - 1] = TileWide::Free;
-            }
-            _ => (),
+    // Reverts up to `count` moves, returning how many were actually reverted.
+    fn undo(&mut self, count: usize) -> usize {
+        let count = count.min(self.cursor);
+        for _ in 0..count {
+            self.cursor -= 1;
+            let delta = self.history[self.cursor].clone();
+            self.apply(&delta, delta.direction.opposite());
         }
+
+        count
     }
 
-    fn move_down(&mut self) {
-        if self.can_move_down(self.robot_y + 1, self.robot_x) {
-            self.do_move_down(self.robot_y + 1, self.robot_x);
-            self.robot_y += 1;
-        }
+    fn redo(&mut self) -> bool {
+        let Some(delta) = self.history.get(self.cursor).cloned() else {
+            return false;
+        };
+
+        self.apply(&delta, delta.direction);
+        self.cursor += 1;
+
+        true
     }
 
-    fn can_move_down(&self, target_y: usize, target_x: usize) -> bool {
-        match self.get_at(target_y, target_x) {
-            TileWide::Free => true,
-            TileWide::Wall => false,
-            TileWide::BoxLeft => {
-                self.can_move_down(target_y + 1, target_x)
-                    && self.can_move_down(target_y + 1, target_x + 1)
-            }
-            TileWide::BoxRight => {
-                self.can_move_down(target_y + 1, target_x)
-                    && self.can_move_down(target_y + 1, target_x - 1)
-            }
+    // Leaves the warehouse as it was right after move `k`, 0 being the initial state.
+    fn jump_to(&mut self, k: usize) {
+        if k < self.cursor {
+            self.undo(self.cursor - k);
         }
+        while self.cursor < k && self.redo() {}
     }
 
-    fn do_move_down(&mut self, target_y: usize, target_x: usize) {
-        match self.get_at(target_y, target_x) {
-            TileWide::BoxLeft => {
-                self.do_move_down(target_y + 1, target_x);
-                self.do_move_down(target_y + 1, target_x + 1);
-                self.map[target_y][target_x] = TileWide::Free;
-                self.map[target_y][target_x + 1] = TileWide::Free;
-                self.map[target_y + 1][target_x] = TileWide::BoxLeft;
-                self.map[target_y + 1][target_x + 1] = TileWide::BoxRight;
-            }
-            TileWide::BoxRight => {
-                self.do_move_down(target_y + 1, target_x);
-                self.do_move_down(target_y + 1, target_x - 1);
-                self.map[target_y][target_x] = TileWide::Free;
-                self.map[target_y][target_x - 1] = TileWide::Free;
-                self.map[target_y + 1][target_x] = TileWide::BoxRight;
-                self.map[target_y + 1][target_x - 1] = TileWide::BoxLeft;
-            }
-            _ => (),
+    // Finds the first move after which `invariant` no longer holds, assuming that once broken it
+    // stays broken. The warehouse is left right after that move so it can be inspected, and 0 means
+    // the initial state is already broken.
+    fn bisect(&mut self, invariant: impl Fn(&Wharehouse) -> bool) -> Option<usize> {
+        self.jump_to(self.history.len());
+        if invariant(self) {
+            return None;
         }
-    }
 
-    fn sum_gps(&self) -> usize {
-        let mut result = 0;
+        self.jump_to(0);
+        if !invariant(self) {
+            return Some(0);
+        }
 
-        self.map.iter().enumerate().for_each(|(row_index, row)| {
-            row.iter().enumerate().for_each(|(col_index, tile)| {
-                if *tile == TileWide::BoxLeft {
-                    result += row_index * 100 + col_index;
-                }
-            });
-        });
+        let mut good = 0;
+        let mut bad = self.history.len();
+        while bad - good > 1 {
+            let middle = (good + bad) / 2;
+            self.jump_to(middle);
+            if invariant(self) {
+                good = middle;
+            } else {
+                bad = middle;
+            }
+        }
 
-        result
+        self.jump_to(bad);
+        Some(bad)
     }
 
-    fn dump_state(&self) {
-        self.map.iter().enumerate().for_each(|(r_index, r)| {
-            println!(
-                "{}",
-                r.iter()
-                    .enumerate()
-                    .map(|(t_index, t)| {
-                        if r_index == self.robot_y && t_index == self.robot_x {
-                            return '@';
-                        }
-                        match t {
-                            TileWide::BoxLeft => '[',
-                            TileWide::BoxRight => ']',
-                            TileWide::Free => '.',
-                            TileWide::Wall => '#',
-                        }
-                    })
-                    .collect::<String>()
-            );
-        });
+    fn sum_gps(&self) -> usize {
+        self.boxes.iter().map(|b| b.y * 100 + b.x).sum()
     }
 
-    fn validate_state(&self) {
-        let mut expected = None;
-        self.map.iter().for_each(|r| {
-            r.iter().for_each(|t| match expected {
-                Some(e) => {
-                    assert_eq!(e, *t);
-                    expected = None;
+    // Checks that the map and the box list agree with each other.
+    fn validate_state(&self) -> Result<()> {
+        for (id, b) in self.boxes.iter().enumerate() {
+            for (x, y) in b.cells() {
+                ensure!(
+                    self.get_at(x, y) == Tile::Box(id),
+                    "Box {} is missing from the map at {},{}",
+                    id,
+                    x,
+                    y
+                );
+            }
+        }
+
+        for (y, row) in self.map.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if let Tile::Box(id) = *tile {
+                    let b = self.boxes[id];
+                    ensure!(
+                        b.y == y && (b.x..b.x + b.width).contains(&x),
+                        "Unexpected box {} at {},{}",
+                        id,
+                        x,
+                        y
+                    );
                 }
-                None => match t {
-                    TileWide::BoxLeft => expected = Some(TileWide::BoxRight),
-                    TileWide::BoxRight => panic!("Unexpected right box"),
-                    _ => (),
-                },
-            });
-        });
+            }
+        }
+
+        ensure!(
+            self.get_at(self.robot_x, self.robot_y) == Tile::Free,
+            "Robot is not on a free tile"
+        );
+
+        Ok(())
     }
 }
@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "16";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -47,31 +47,44 @@ const TEST_2: &str = "\
 #################
 ";
 
+fn parse(input: &str) -> Result<Puzzle> {
+    Puzzle::new(&mut input.lines().map(String::from))
+}
+
+fn part1(puzzle: &Puzzle) -> Result<Puzzle> {
+    let mut puzzle = puzzle.clone();
+    puzzle.solve();
+
+    Ok(puzzle)
+}
+
 fn main() -> Result<()> {
     start_day(DAY);
 
-    fn part1<R: BufRead>(reader: R) -> Result<Puzzle> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let mut puzzle = Puzzle::new(&mut lines);
-        puzzle.solve();
-
-        Ok(puzzle)
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |puzzle| Ok(part1(puzzle)?.get_lower_cost()))?;
+        bench(DAY, 2, parse, |puzzle| {
+            Ok(part1(puzzle)?.count_tiles_in_best_paths())
+        })?;
+        return Ok(());
     }
 
     println!("=== Test 1 ===");
-    let test1_result = time_snippet!(part1(BufReader::new(TEST_1.as_bytes()))?);
+    let test1_result = time_snippet!(part1(&parse(TEST_1)?)?);
     assert_eq!(7036, test1_result.get_lower_cost());
     assert_eq!(45, test1_result.count_tiles_in_best_paths());
 
     println!("=== Test 2 ===");
-    let test2_result = time_snippet!(part1(BufReader::new(TEST_2.as_bytes()))?);
+    let test2_result = time_snippet!(part1(&parse(TEST_2)?)?);
     assert_eq!(11048, test2_result.get_lower_cost());
     assert_eq!(64, test2_result.count_tiles_in_best_paths());
 
     println!("=== Puzzle ===");
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let puzzle_result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let puzzle_result = time_snippet!(part1(&input)?);
 
     println!("=== Part 1 ===");
     println!("Result = {}", puzzle_result.get_lower_cost());
@@ -93,6 +106,7 @@ enum Tile {
     End,   // E
 }
 
+#[derive(Clone)]
 struct Puzzle {
     map: Vec<Vec<Tile>>,
     best_path_tile: Vec<Vec<bool>>,
@@ -203,7 +217,7 @@ impl State {
 }
 
 impl Puzzle {
-    fn new(lines: &mut impl Iterator<Item = String>) -> Self {
+    fn new(lines: &mut impl Iterator<Item = String>) -> Result<Self> {
         let mut map: Vec<Vec<Tile>> = vec![];
         let mut start_x = None;
         let mut start_y = None;
@@ -216,48 +230,44 @@ impl Puzzle {
                 line.chars()
                     .enumerate()
                     .map(|(x, c)| match c {
-                        '#' => Tile::Wall,
-                        '.' => Tile::Free,
+                        '#' => Ok(Tile::Wall),
+                        '.' => Ok(Tile::Free),
                         'S' => {
                             start_x = Some(x);
                             start_y = Some(y);
-                            Tile::Start
+                            Ok(Tile::Start)
                         }
-                        'E' => Tile::End,
-                        _ => panic!("Unexpected tile"),
+                        'E' => Ok(Tile::End),
+                        _ => Err(anyhow!("Unexpected tile [{}]", c)),
                     })
-                    .collect(),
+                    .collect::<Result<_>>()?,
             );
         }
 
         let row_count = map.len();
-        let col_count = map[0].len();
-
-        Self {
+        let col_count = map.first().map_or(0, Vec::len);
+        ensure!(
+            map.iter().all(|row| row.len() == col_count),
+            "Maze rows have different lengths"
+        );
+        // The solver walks without bounds checks, relying on the walls around the maze.
+        ensure!(
+            map.iter().enumerate().all(|(y, row)| {
+                row.iter().enumerate().all(|(x, tile)| {
+                    *tile == Tile::Wall
+                        || (y > 0 && y + 1 < row_count && x > 0 && x + 1 < col_count)
+                })
+            }),
+            "Maze is not surrounded by walls"
+        );
+
+        Ok(Self {
             map,
-            start_x: start_x.expect("Start X was found."),
-            start_y: start_y.expect("Start Y was found."),
+            start_x: start_x.context("Missing start tile")?,
+            start_y: start_y.context("Missing start tile")?,
             lowest_cost: None,
             best_path_tile: vec![vec![false; col_count]; row_count],
-        }
-    }
-
-    fn dump_state(&self) {
-        self.map.iter().for_each(|r| {
-            println!(
-                "{}",
-                r.iter()
-                    .map(|t| {
-                        match t {
-                            Tile::Wall => '#',
-                            Tile::Free => '.',
-                            Tile::Start => 'S',
-                            Tile::End => 'E',
-                        }
-                    })
-                    .collect::<String>()
-            );
-        });
+        })
     }
 
     fn solve(&mut self) {
@@ -341,29 +351,6 @@ impl Puzzle {
             self.best_path_tile[*y][*x] = true;
         }
     }
-
-    fn dump_best_paths(&self) {
-        self.map.iter().enumerate().for_each(|(row, r)| {
-            println!(
-                "{}",
-                r.iter()
-                    .enumerate()
-                    .map(|(col, t)| {
-                        if self.best_path_tile[row][col] {
-                            'O'
-                        } else {
-                            match t {
-                                Tile::Wall => '#',
-                                Tile::Free => '.',
-                                Tile::Start => 'S',
-                                Tile::End => 'E',
-                            }
-                        }
-                    })
-                    .collect::<String>()
-            );
-        });
-    }
 }
 
 struct PathTracker {
@@ -384,7 +371,7 @@ impl PathTracker {
 
         self.cost_per_direction
             .entry((state.x, state.y))
-            .or_insert(HashMap::new())
+            .or_default()
             .entry(state.direction)
             .and_modify(|c| {
                 if *c > state.cost {
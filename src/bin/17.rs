@@ -1,15 +1,16 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use itertools::Itertools;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use std::ops::BitXor;
-use std::usize;
 
 const DAY: &str = "17";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+// Real programs halt after a few hundred instructions.
+const MAX_STEPS: usize = 100_000;
 
 const EXAMPLE_1: &str = "\
 Register A: 0
@@ -67,7 +68,7 @@ Register C: 0
 Program: 0,3,5,4,3,0
 ";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Computer {
     ins_ptr: usize,
     reg_a: usize,
@@ -78,47 +79,63 @@ struct Computer {
 }
 
 impl Computer {
-    fn new(lines: &mut impl Iterator<Item = String>) -> Self {
-        let reg_a = parse_reg(lines);
-        let reg_b = parse_reg(lines);
-        let reg_c = parse_reg(lines);
+    fn new(lines: &mut impl Iterator<Item = String>) -> Result<Self> {
+        let reg_a = parse_reg(lines)?;
+        let reg_b = parse_reg(lines)?;
+        let reg_c = parse_reg(lines)?;
 
-        lines.next().expect("Empty line");
+        lines.next().context("Missing empty line")?;
 
         let program = lines
             .next()
-            .expect("Program line")
-            .split(": ")
-            .collect_vec()[1]
+            .context("Missing program line")?
+            .split_once(": ")
+            .context("Invalid program line")?
+            .1
             .split(",")
-            .flat_map(|s| s.parse::<u8>())
-            .collect_vec();
+            .map(|s| s.parse::<u8>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Invalid program")?;
+        ensure!(
+            program.len() % 2 == 0,
+            "Program has an opcode without operand"
+        );
+        for (opcode, operand) in program.iter().tuples() {
+            ensure!(*opcode < 8 && *operand < 8, "Program values must be 3 bits");
+            ensure!(
+                *operand != 7 || matches!(opcode, 1 | 3 | 4),
+                "Opcode {} doesn't take a combo operand of 7",
+                opcode
+            );
+        }
 
-        Self {
+        Ok(Self {
             ins_ptr: 0,
             reg_a,
             reg_b,
             reg_c,
             program,
             output: vec![],
-        }
+        })
     }
     fn get_output(&self) -> String {
         self.output.iter().join(",")
     }
 
-    fn execute(&mut self) {
-        while self.ins_ptr < self.program.len() {
+    fn execute(&mut self) -> Result<()> {
+        let mut steps = 0;
+        // A jump can land on the last value, which halts like running past the end.
+        while self.ins_ptr + 1 < self.program.len() {
+            steps += 1;
+            ensure!(steps <= MAX_STEPS, "Program doesn't halt");
+
             let opcode = self.program[self.ins_ptr];
             let operand = self.program[self.ins_ptr + 1];
 
             match opcode {
                 // adv
                 0 => {
-                    let combo = self.combo(operand);
-                    let base: usize = 2;
-                    let result = self.reg_a / base.pow(combo.try_into().unwrap());
-                    self.reg_a = result;
+                    self.reg_a = self.divide(operand);
                 }
 
                 // bxl
@@ -156,24 +173,28 @@ impl Computer {
 
                 // bdv
                 6 => {
-                    let combo = self.combo(operand);
-                    let base: usize = 2;
-                    let result = self.reg_a / base.pow(combo.try_into().unwrap());
-                    self.reg_b = result;
+                    self.reg_b = self.divide(operand);
                 }
 
                 // cdv
                 7 => {
-                    let combo = self.combo(operand);
-                    let base: usize = 2;
-                    let result = self.reg_a / base.pow(combo.try_into().unwrap());
-                    self.reg_c = result;
+                    self.reg_c = self.divide(operand);
                 }
 
                 x => todo!("Opcode {} not implemented.", x),
             }
             self.ins_ptr += 2;
         }
+
+        Ok(())
+    }
+
+    // Register A divided by 2 to the power of the combo operand, which can shift out every bit.
+    fn divide(&self, operand: u8) -> usize {
+        u32::try_from(self.combo(operand))
+            .ok()
+            .and_then(|shift| self.reg_a.checked_shr(shift))
+            .unwrap_or(0)
     }
 
     fn combo(&self, operand: u8) -> usize {
@@ -192,49 +213,88 @@ impl Computer {
     }
 }
 
-fn parse_reg(lines: &mut impl Iterator<Item = String>) -> usize {
-    lines.next().expect("Reg line").split(": ").collect_vec()[1]
-        .parse::<usize>()
-        .expect("I can parse the register.")
+fn parse_reg(lines: &mut impl Iterator<Item = String>) -> Result<usize> {
+    let line = lines.next().context("Missing register line")?;
+    line.split_once(": ")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .with_context(|| format!("Invalid register line [{}]", line))
+}
+
+fn parse(input: &str) -> Result<Computer> {
+    Computer::new(&mut input.lines().map(String::from))
+}
+
+fn part1(computer: &Computer) -> Result<Computer> {
+    let mut comp = computer.clone();
+    comp.execute()?;
+    Ok(comp)
+}
+
+fn part2(computer: &Computer) -> Result<usize> {
+    let mut comp = computer.clone();
+
+    let match_reversed: Vec<u8> = comp.program.clone().into_iter().rev().collect();
+    let mut reg_a_set: Vec<usize> = vec![0];
+
+    for expected in match_reversed.iter() {
+        let mut new_candidates = vec![];
+        for a in reg_a_set {
+            let Some(base) = a.checked_mul(8) else {
+                continue;
+            };
+            for i in 0..8 {
+                let candidate = base + i;
+                comp.reset();
+                comp.reg_a = candidate;
+                comp.execute()?;
+
+                if comp.output.first() == Some(expected) {
+                    new_candidates.push(candidate);
+                }
+            }
+        }
+
+        reg_a_set = new_candidates;
+    }
+
+    reg_a_set
+        .into_iter()
+        .min()
+        .context("No value of register A outputs the program")
 }
 
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |computer| Ok(part1(computer)?.get_output()))?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<Computer> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let mut comp = Computer::new(&mut lines);
-        comp.execute();
-        Ok(comp)
-    }
-
-    assert_eq!(1, part1(BufReader::new(EXAMPLE_1.as_bytes()))?.reg_b);
+    assert_eq!(1, part1(&parse(EXAMPLE_1)?)?.reg_b);
 
-    assert_eq!(
-        "0,1,2",
-        part1(BufReader::new(EXAMPLE_2.as_bytes()))?.get_output()
-    );
+    assert_eq!("0,1,2", part1(&parse(EXAMPLE_2)?)?.get_output());
 
     {
-        let test3 = part1(BufReader::new(EXAMPLE_3.as_bytes()))?;
+        let test3 = part1(&parse(EXAMPLE_3)?)?;
         assert_eq!("4,2,5,6,7,7,7,7,3,1,0", test3.get_output());
         assert_eq!(0, test3.reg_a);
     }
 
-    assert_eq!(26, part1(BufReader::new(EXAMPLE_4.as_bytes()))?.reg_b);
+    assert_eq!(26, part1(&parse(EXAMPLE_4)?)?.reg_b);
 
-    assert_eq!(44354, part1(BufReader::new(EXAMPLE_5.as_bytes()))?.reg_b);
+    assert_eq!(44354, part1(&parse(EXAMPLE_5)?)?.reg_b);
 
-    assert_eq!(
-        "4,6,3,5,6,3,5,2,1,0",
-        part1(BufReader::new(TEST_1.as_bytes()))?.get_output()
-    );
+    assert_eq!("4,6,3,5,6,3,5,2,1,0", part1(&parse(TEST_1)?)?.get_output());
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     let output = result.get_output();
     println!("Result = {}", output);
     assert_eq!("3,1,4,3,1,7,1,6,3", output);
@@ -243,46 +303,9 @@ fn main() -> Result<()> {
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-        let mut comp = Computer::new(&mut lines);
-
-        let match_reversed: Vec<u8> = comp.program.clone().into_iter().rev().collect();
-        let mut reg_a_set: Vec<usize> = vec![];
-
-        reg_a_set.push(0);
-        for pos in 0..match_reversed.len() {
-            let new_candidates = reg_a_set
-                .into_iter()
-                .flat_map(|a| {
-                    let mut res = vec![];
-                    for i in 0..8 {
-                        let candidate = a.rotate_left(3) + i;
-                        comp.reset();
-                        comp.reg_a = candidate;
-                        comp.execute();
-
-
-                        if comp.output[0] == match_reversed[pos] {
-                            res.push(candidate);
-                        }
-                    }
-
-                    res
-                })
-                .collect();
-
-            reg_a_set = new_candidates;
-        }
-
-        reg_a_set.sort();
-        Ok(reg_a_set[0])
-    }
-
-    assert_eq!(117440, part2(BufReader::new(TEST_2.as_bytes()))?);
+    assert_eq!(117440, part2(&parse(TEST_2)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     //endregion
 
@@ -1,9 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::terminal::{fps_from_args, print_frame, Animation, Cell, Color, Scene};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "18";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -57,8 +58,15 @@ impl Puzzle {
         }
     }
 
-    fn push_byte(&mut self, row: usize, col: usize) {
+    fn push_byte(&mut self, row: usize, col: usize) -> Result<()> {
+        ensure!(
+            row < self.size && col < self.size,
+            "Byte {},{} falls outside the memory space",
+            col,
+            row
+        );
         self.tiles[row][col] = Tile::Ocuppied;
+        Ok(())
     }
 
     fn solve(&mut self) -> Option<usize> {
@@ -96,14 +104,7 @@ impl Puzzle {
     }
 
     fn _dump_state(&self) {
-        self.tiles.iter().for_each(|row| {
-            row.iter().for_each(|t| match t {
-                Tile::Free => print!("."),
-                Tile::Ocuppied => print!("#"),
-                Tile::Step => print!("O"),
-            });
-            println!();
-        });
+        print_frame(self);
     }
 
     fn clear(&mut self) {
@@ -117,66 +118,163 @@ impl Puzzle {
     }
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+impl Scene for Puzzle {
+    fn width(&self) -> usize {
+        self.size
+    }
 
-    //region Part 1
-    println!("=== Part 1 ===");
+    fn height(&self) -> usize {
+        self.size
+    }
 
-    fn part1<R: BufRead>(ram_size: usize, byte_count: usize, reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-
-        let mut puzzle = Puzzle::new(ram_size);
-        for _ in 0..byte_count {
-            let coords: Vec<usize> = lines
-                .next()
-                .expect("Line was found")
-                .split(",")
-                .map(|v| v.parse::<usize>().expect("Parseable numer."))
-                .collect();
-            puzzle.push_byte(coords[1], coords[0]);
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        match self.tiles[y][x] {
+            Tile::Free => Cell::new('.', Color::DarkGrey),
+            Tile::Ocuppied => Cell::new('#', Color::Red),
+            Tile::Step => Cell::new('O', Color::Green),
         }
+    }
+}
 
-        Ok(puzzle.solve().expect("Failed to solve puzzle."))
+// Well above the 71 of the real memory, to keep a stray coordinate from allocating too much.
+const MAX_MEMORY_SIZE: usize = 1000;
+
+// The falling bytes as (x, y), in order, in a memory just big enough to hold all of them.
+struct Memory {
+    size: usize,
+    bytes: Vec<(usize, usize)>,
+}
+
+impl Memory {
+    // As many as the puzzle says for the example and the real memory, and a fifth of any other.
+    fn part1_byte_count(&self) -> usize {
+        match self.size {
+            7 => 12,
+            71 => 1024,
+            size => size * size / 5,
+        }
     }
+}
 
-    assert_eq!(22, part1(7, 12, BufReader::new(TEST.as_bytes()))?);
+fn parse(input: &str) -> Result<Memory> {
+    let bytes: Vec<(usize, usize)> = input
+        .lines()
+        .map(|line| {
+            line.split_once(',')
+                .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                .with_context(|| format!("Invalid byte position [{}]", line))
+        })
+        .collect::<Result<_>>()?;
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(71, 1024, input_file)?);
-    println!("Result = {}", result);
-    assert_eq!(324, result);
-    //endregion
+    let size = bytes
+        .iter()
+        .map(|&(x, y)| x.max(y) + 1)
+        .max()
+        .context("No bytes")?;
+    ensure!(
+        size <= MAX_MEMORY_SIZE,
+        "Memory of size {} is larger than {}",
+        size,
+        MAX_MEMORY_SIZE
+    );
 
-    //region Part 2
-    println!("\n=== Part 2 ===");
+    Ok(Memory { size, bytes })
+}
 
-    fn part2<R: BufRead>(ram_size: usize, reader: R) -> Result<String> {
-        let mut puzzle = Puzzle::new(ram_size);
+fn part1(memory: &Memory) -> Result<usize> {
+    let byte_count = memory.part1_byte_count();
+    ensure!(
+        memory.bytes.len() >= byte_count,
+        "Expected at least {} bytes",
+        byte_count
+    );
 
-        let mut lines = reader.lines().map_while(Result::ok);
-        loop {
-            let coords: Vec<usize> = lines
-                .next()
-                .expect("Line was found")
-                .split(",")
-                .map(|v| v.parse::<usize>().expect("Parseable numer."))
-                .collect();
+    let mut puzzle = Puzzle::new(memory.size);
+    for &(x, y) in memory.bytes.iter().take(byte_count) {
+        puzzle.push_byte(y, x)?;
+    }
 
-            puzzle.push_byte(coords[1], coords[0]);
+    puzzle.solve().context("The exit can't be reached")
+}
 
-            if puzzle.solve().is_none() {
-                return Ok(format!("{},{}", coords[0], coords[1]));
-            }
-            puzzle.clear();
+fn part2(memory: &Memory) -> Result<String> {
+    let mut puzzle = Puzzle::new(memory.size);
+
+    for &(x, y) in memory.bytes.iter() {
+        puzzle.push_byte(y, x)?;
+
+        if puzzle.solve().is_none() {
+            return Ok(format!("{},{}", x, y));
         }
+        puzzle.clear();
     }
 
-    assert_eq!("6,1", part2(7, BufReader::new(TEST.as_bytes()))?);
+    bail!("The exit is never blocked")
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
+    // Drops the bytes one by one, showing what can still be reached, until the exit is cut off.
+    // cargo run --release --bin 18 -- --animate 30
+    if let Some(fps) = fps_from_args(&std::env::args().skip(1).collect::<Vec<_>>())? {
+        let memory = parse(&fs::read_to_string(INPUT_FILE)?)?;
+        let mut bytes = memory.bytes.into_iter();
+        let mut puzzle = Puzzle::new(memory.size);
+        let mut blocked = false;
+        return Animation::new(fps).run(&mut puzzle, |puzzle| {
+            let Some((x, y)) = bytes.next().filter(|_| !blocked) else {
+                return false;
+            };
+            puzzle.clear();
+            if puzzle.push_byte(y, x).is_err() {
+                return false;
+            }
+            blocked = puzzle.solve().is_none();
+            true
+        });
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(71, input_file)?);
+    assert_eq!(22, part1(&parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    assert_eq!(324, result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!("6,1", part2(&parse(TEST)?)?);
+
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     assert_eq!("46,23", result);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adv_code_2024::generate::generator;
+
+    #[test]
+    fn generated_memory_sets_its_own_size() {
+        let memory = parse(&generator(DAY).unwrap().generate(1, 30)).unwrap();
+        assert_eq!(30, memory.size);
+        assert!(part1(&memory).is_ok());
+        assert!(part2(&memory).is_ok());
+    }
+}
@@ -1,10 +1,10 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 const DAY: &str = "19";
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -22,6 +22,44 @@ brgr
 bbrgwb
 ";
 
+struct Onsen {
+    towels: Vec<String>,
+    designs: Vec<String>,
+}
+
+fn parse(input: &str) -> Result<Onsen> {
+    let mut lines = input.lines();
+
+    let towels: Vec<String> = lines
+        .next()
+        .context("Missing line with towels")?
+        .split(",")
+        .map(|s| s.trim().to_owned())
+        .collect();
+    ensure!(towels.iter().all(|t| !t.is_empty()), "Empty towel pattern");
+    ensure!(
+        towels.iter().all(|t| is_striped(t)),
+        "Towels can only have white, blue, black, red or green stripes"
+    );
+
+    ensure!(
+        lines.next().is_some_and(str::is_empty),
+        "Expected an empty line after the towels"
+    );
+
+    let designs: Vec<String> = lines.map(String::from).collect();
+    ensure!(
+        designs.iter().all(|d| is_striped(d)),
+        "Designs can only have white, blue, black, red or green stripes"
+    );
+
+    Ok(Onsen { towels, designs })
+}
+
+fn is_striped(pattern: &str) -> bool {
+    pattern.chars().all(|c| "wubrg".contains(c))
+}
+
 #[derive(Debug)]
 struct Linen {
     towel_map: HashMap<String, Vec<String>>,
@@ -67,24 +105,26 @@ impl Linen {
         false
     }
 
-    fn possible_combo(&mut self, line: &str) -> usize {
+    fn possible_combo(&mut self, line: &str) -> Result<usize> {
         if line.is_empty() {
-            return 1;
+            return Ok(1);
         }
 
         if self.impossible_towel.contains(line) {
-            return 0;
+            return Ok(0);
         }
 
         if let Some(c) = self.count_cache.get(line) {
-            return *c;
+            return Ok(*c);
         }
 
-        let mut count = 0;
+        let mut count: usize = 0;
         if let Some(towels) = self.towel_map.get_mut(&line[0..1]) {
             for towel in towels.clone().iter() {
                 if line.starts_with(towel) {
-                    count += self.possible_combo(&line[towel.len()..]);
+                    count = count
+                        .checked_add(self.possible_combo(&line[towel.len()..])?)
+                        .context("Too many arrangements to count")?;
                 }
             }
         }
@@ -95,44 +135,55 @@ impl Linen {
 
         self.count_cache.insert(line.to_owned(), count);
 
-        count
+        Ok(count)
     }
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+fn part1(onsen: &Onsen) -> Result<usize> {
+    let mut linen = Linen::new(onsen.towels.clone());
 
-    //region Part 1
-    println!("=== Part 1 ===");
+    let mut count = 0;
+    for l in onsen.designs.iter() {
+        if linen.can_be_made(l) {
+            count += 1;
+        }
+    }
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
+    Ok(count)
+}
 
-        let towels = lines
-            .next()
-            .expect("Line with towels")
-            .split(",")
-            .map(|s| s.trim().to_owned())
-            .collect();
+fn part2(onsen: &Onsen) -> Result<usize> {
+    let mut linen = Linen::new(onsen.towels.clone());
 
-        let mut linen = Linen::new(towels);
+    let mut count: usize = 0;
+    for l in onsen.designs.iter() {
+        let partial = linen.possible_combo(l)?;
+        count = count
+            .checked_add(partial)
+            .context("Too many arrangements to count")?;
+    }
 
-        lines.next().expect("Empty line");
+    Ok(count)
+}
 
-        let mut count = 0;
-        for l in lines {
-            if linen.can_be_made(&l) {
-                count += 1;
-            }
-        }
+fn main() -> Result<()> {
+    start_day(DAY);
 
-        Ok(count)
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
     }
 
-    assert_eq!(6, part1(BufReader::new(TEST.as_bytes()))?);
+    //region Part 1
+    println!("=== Part 1 ===");
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    assert_eq!(6, part1(&parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     assert_eq!(306, result);
     //endregion
@@ -140,33 +191,9 @@ fn main() -> Result<()> {
     //region Part 2
     println!("\n=== Part 2 ===");
 
-    fn part2<R: BufRead>(reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
-
-        let towels = lines
-            .next()
-            .expect("Line with towels")
-            .split(",")
-            .map(|s| s.trim().to_owned())
-            .collect();
-
-        let mut linen = Linen::new(towels);
-
-        lines.next().expect("Empty line");
-
-        let mut count = 0;
-        for l in lines {
-            let partial = linen.possible_combo(&l);
-            count += partial;
-        }
-
-        Ok(count)
-    }
-
-    assert_eq!(16, part2(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(16, part2(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part2(input_file)?);
+    let result = time_snippet!(part2(&input)?);
     println!("Result = {}", result);
     assert_eq!(604622004681855, result);
 
@@ -1,9 +1,9 @@
+use adv_code_2024::bench::{bench, bench_requested};
 use adv_code_2024::*;
 use anyhow::*;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use std::rc::Rc;
 
 const DAY: &str = "20";
@@ -27,7 +27,7 @@ const TEST: &str = "\
 ###############
 ";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct RaceTrack {
     row_count: usize,
     col_count: usize,
@@ -36,7 +36,7 @@ struct RaceTrack {
     map: Vec<Vec<Tile>>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum Tile {
     Wall,
     Track,
@@ -44,7 +44,7 @@ enum Tile {
 }
 
 impl RaceTrack {
-    fn new(lines: &mut impl Iterator<Item = String>) -> Self {
+    fn new(lines: &mut impl Iterator<Item = String>) -> Result<Self> {
         let mut start = Option::None;
         let mut end = Option::None;
         let mut map: Vec<Vec<Tile>> = vec![];
@@ -55,38 +55,45 @@ impl RaceTrack {
                     .chars()
                     .enumerate()
                     .map(|(col_index, c)| match c {
-                        '#' => Tile::Wall,
-                        '.' => Tile::Track,
+                        '#' => Ok(Tile::Wall),
+                        '.' => Ok(Tile::Track),
                         'S' => {
                             start = Some((row_index, col_index));
-                            Tile::Track
+                            Ok(Tile::Track)
                         }
                         'E' => {
                             end = Some((row_index, col_index));
-                            Tile::Track
+                            Ok(Tile::Track)
                         }
-                        x => panic!("Unknown tile type {x}"),
+                        x => Err(anyhow!("Unknown tile type {x}")),
                     })
-                    .collect(),
+                    .collect::<Result<_>>()?,
             );
         }
-        Self {
+
+        let col_count = map.first().map_or(0, Vec::len);
+        ensure!(
+            map.iter().all(|row| row.len() == col_count),
+            "Race track rows have different lengths"
+        );
+
+        Ok(Self {
             row_count: map.len(),
-            col_count: map[0].len(),
-            start: start.expect("Start position expected in map"),
-            end: end.expect("End position expected in map"),
+            col_count,
+            start: start.context("Start position expected in map")?,
+            end: end.context("End position expected in map")?,
             map,
-        }
+        })
     }
 
-    fn solve(&mut self) {
+    fn solve(&mut self) -> Result<()> {
         let mut visited = vec![vec![false; self.col_count]; self.row_count];
         let mut tips = vec![Rc::new(PathStep::new(self.start))];
         let mut path_length = 1;
 
         loop {
             if tips.is_empty() {
-                panic!("Can't find solution for race tracke.");
+                bail!("Can't find solution for race track.");
             }
 
             let mut new_tips = vec![];
@@ -116,7 +123,7 @@ impl RaceTrack {
                     }
 
                     //self._dump_track();
-                    return;
+                    return Ok(());
                 }
             }
             path_length += 1;
@@ -189,7 +196,7 @@ impl PathStep {
         }
     }
 
-    fn new_child<'a>(pos: (usize, usize), parent: &Rc<PathStep>) -> PathStep {
+    fn new_child(pos: (usize, usize), parent: &Rc<PathStep>) -> PathStep {
         PathStep {
             pos,
             parent: Option::Some(parent.clone()),
@@ -197,32 +204,45 @@ impl PathStep {
     }
 }
 
-fn main() -> Result<()> {
-    start_day(DAY);
+fn parse(input: &str) -> Result<RaceTrack> {
+    RaceTrack::new(&mut input.lines().map(String::from))
+}
 
-    //region Part 1
-    println!("=== Part 1 ===");
+fn part1(pico_seconds: usize, racetrack: &RaceTrack) -> Result<usize> {
+    let mut racetrack = racetrack.clone();
 
-    fn part1<R: BufRead>(pico_seconds: usize, reader: R) -> Result<usize> {
-        let mut lines = reader.lines().map_while(Result::ok);
+    racetrack.solve()?;
 
-        let mut racetrack = RaceTrack::new(&mut lines);
+    Ok(racetrack.count_cheats(pico_seconds))
+}
 
-        racetrack.solve();
+// fn part2(racetrack: &RaceTrack) -> Result<usize> {
+//     Ok(0)
+// }
 
-        Ok(racetrack.count_cheats(pico_seconds))
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |input| part1(100, input))?;
+        return Ok(());
     }
 
+    //region Part 1
+    println!("=== Part 1 ===");
+
     // Set the expected answer for the test input
-    assert_eq!(1, part1(64, BufReader::new(TEST.as_bytes()))?);
-    assert_eq!(2, part1(40, BufReader::new(TEST.as_bytes()))?);
-    assert_eq!(3, part1(38, BufReader::new(TEST.as_bytes()))?);
-    assert_eq!(4, part1(36, BufReader::new(TEST.as_bytes()))?);
-    assert_eq!(5, part1(20, BufReader::new(TEST.as_bytes()))?);
-    assert_eq!(8, part1(12, BufReader::new(TEST.as_bytes()))?);
-
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(100, input_file)?);
+    assert_eq!(1, part1(64, &parse(TEST)?)?);
+    assert_eq!(2, part1(40, &parse(TEST)?)?);
+    assert_eq!(3, part1(38, &parse(TEST)?)?);
+    assert_eq!(4, part1(36, &parse(TEST)?)?);
+    assert_eq!(5, part1(20, &parse(TEST)?)?);
+    assert_eq!(8, part1(12, &parse(TEST)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(100, &input)?);
     println!("Result = {}", result);
     assert_eq!(1323, result);
     //endregion
@@ -230,14 +250,9 @@ fn main() -> Result<()> {
     //region Part 2
     // println!("\n=== Part 2 ===");
     //
-    // fn part2<R: BufRead>(reader: R) -> Result<usize> {
-    //     Ok(0)
-    // }
-    //
-    // assert_eq!(0, part2(BufReader::new(TEST.as_bytes()))?);
+    // assert_eq!(0, part2(&parse(TEST)?)?);
     //
-    // let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    // let result = time_snippet!(part2(input_file)?);
+    // let result = time_snippet!(part2(&input)?);
     // println!("Result = {}", result);
     //endregion
 
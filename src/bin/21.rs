@@ -0,0 +1,227 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
+use anyhow::*;
+use code_timing_macros::time_snippet;
+use const_format::concatcp;
+use std::collections::HashMap;
+use std::fs;
+
+const DAY: &str = "21";
+const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+
+const TEST: &str = "\
+029A
+980A
+179A
+456A
+379A
+";
+
+const NUMERIC_KEYPAD: [&str; 4] = ["789", "456", "123", " 0A"];
+const DIRECTIONAL_KEYPAD: [&str; 2] = [" ^A", "<v>"];
+
+struct Keypad {
+    keys: HashMap<char, (i32, i32)>,
+    gap: (i32, i32),
+}
+
+impl Keypad {
+    fn new(rows: &[&str]) -> Keypad {
+        let mut keys = HashMap::new();
+        let mut gap = (0, 0);
+        for (row, line) in rows.iter().enumerate() {
+            for (col, key) in line.chars().enumerate() {
+                let position = (row as i32, col as i32);
+                if key == ' ' {
+                    gap = position;
+                } else {
+                    keys.insert(key, position);
+                }
+            }
+        }
+
+        Keypad { keys, gap }
+    }
+
+    fn position(&self, key: char) -> Result<(i32, i32)> {
+        self.keys
+            .get(&key)
+            .copied()
+            .ok_or_else(|| anyhow!("No key [{}] on the keypad", key))
+    }
+
+    // The shortest ways to move from one key to the other and press it. Zigzagging is never
+    // shorter once the moves are typed by another robot, so only the two L shaped paths that don't
+    // go over the gap are kept.
+    fn paths(&self, from: char, to: char) -> Result<Vec<String>> {
+        let (from_row, from_col) = self.position(from)?;
+        let (to_row, to_col) = self.position(to)?;
+
+        let vertical =
+            if to_row > from_row { "v" } else { "^" }.repeat(from_row.abs_diff(to_row) as usize);
+        let horizontal =
+            if to_col > from_col { ">" } else { "<" }.repeat(from_col.abs_diff(to_col) as usize);
+
+        let mut paths = vec![];
+        if (from_row, to_col) != self.gap {
+            paths.push(format!("{}{}A", horizontal, vertical));
+        }
+        if (to_row, from_col) != self.gap {
+            paths.push(format!("{}{}A", vertical, horizontal));
+        }
+        paths.dedup();
+
+        Ok(paths)
+    }
+}
+
+// A chain of robots: the first one types on the numeric keypad, the others on the directional
+// keypad of the robot before them, and we type on the last directional keypad.
+struct Chain {
+    numeric: Keypad,
+    directional: Keypad,
+    robots: usize,
+    // Presses needed to type `to` after `from`, on a directional keypad `depth` robots away from us.
+    cache: HashMap<(char, char, usize), usize>,
+}
+
+impl Chain {
+    fn new(robots: usize) -> Chain {
+        Chain {
+            numeric: Keypad::new(&NUMERIC_KEYPAD),
+            directional: Keypad::new(&DIRECTIONAL_KEYPAD),
+            robots,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn presses(&mut self, code: &str) -> Result<usize> {
+        let mut total = 0;
+        let mut from = 'A';
+        for to in code.chars() {
+            let mut best = usize::MAX;
+            for path in self.numeric.paths(from, to)? {
+                best = best.min(self.sequence_presses(&path, self.robots)?);
+            }
+            total += best;
+            from = to;
+        }
+
+        Ok(total)
+    }
+
+    fn sequence_presses(&mut self, sequence: &str, depth: usize) -> Result<usize> {
+        if depth == 0 {
+            return Ok(sequence.len());
+        }
+
+        let mut total = 0;
+        let mut from = 'A';
+        for to in sequence.chars() {
+            total += self.key_presses(from, to, depth)?;
+            from = to;
+        }
+
+        Ok(total)
+    }
+
+    fn key_presses(&mut self, from: char, to: char, depth: usize) -> Result<usize> {
+        if let Some(presses) = self.cache.get(&(from, to, depth)) {
+            return Ok(*presses);
+        }
+
+        let mut best = usize::MAX;
+        for path in self.directional.paths(from, to)? {
+            best = best.min(self.sequence_presses(&path, depth - 1)?);
+        }
+        self.cache.insert((from, to, depth), best);
+
+        Ok(best)
+    }
+}
+
+struct Code {
+    keys: String,
+    // The numeric part of the code.
+    value: usize,
+}
+
+fn parse(input: &str) -> Result<Vec<Code>> {
+    input
+        .lines()
+        .map(|line| {
+            Ok(Code {
+                keys: line.to_owned(),
+                value: line
+                    .trim_end_matches('A')
+                    .parse()
+                    .with_context(|| format!("Invalid code [{}]", line))?,
+            })
+        })
+        .collect()
+}
+
+fn complexity(codes: &[Code], robots: usize) -> Result<usize> {
+    let mut chain = Chain::new(robots);
+    let mut result: usize = 0;
+    for code in codes {
+        result = chain
+            .presses(&code.keys)?
+            .checked_mul(code.value)
+            .and_then(|complexity| result.checked_add(complexity))
+            .context("Complexity is too large")?;
+    }
+
+    Ok(result)
+}
+
+fn part1(codes: &[Code]) -> Result<usize> {
+    complexity(codes, 2)
+}
+
+fn part2(codes: &[Code]) -> Result<usize> {
+    complexity(codes, 25)
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |codes| part1(codes))?;
+        bench(DAY, 2, parse, |codes| part2(codes))?;
+        return Ok(());
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(126384, part1(&parse(TEST)?)?);
+
+    let mut chain = Chain::new(2);
+    assert_eq!(
+        vec![68, 60, 68, 64, 64],
+        TEST.lines()
+            .map(|code| chain.presses(code))
+            .collect::<Result<Vec<_>>>()?
+    );
+    assert_eq!(12, Chain::new(0).presses("029A")?);
+    assert_eq!(28, Chain::new(1).presses("029A")?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!(154115708116294, part2(&parse(TEST)?)?);
+
+    let result = time_snippet!(part2(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
+use anyhow::*;
+use code_timing_macros::time_snippet;
+use const_format::concatcp;
+use std::fs;
+
+const DAY: &str = "22";
+const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+
+const TEST: &str = "\
+1
+10
+100
+2024
+";
+const TEST_2: &str = "\
+1
+2
+3
+2024
+";
+
+const PRUNE: u64 = 16777216;
+const STEPS: usize = 2000;
+
+// Changes go from -9 to 9, so a sequence of four fits in a base 19 number.
+const SEQUENCE_COUNT: usize = 19 * 19 * 19 * 19;
+
+fn next_secret(secret: u64) -> u64 {
+    let secret = ((secret * 64) ^ secret) % PRUNE;
+    let secret = ((secret / 32) ^ secret) % PRUNE;
+    ((secret * 2048) ^ secret) % PRUNE
+}
+
+fn parse(input: &str) -> Result<Vec<u64>> {
+    input
+        .lines()
+        .map(|line| {
+            let secret = line
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid secret number [{}]", line))?;
+            ensure!(secret < PRUNE, "Secret number [{}] is not pruned", line);
+            Ok(secret)
+        })
+        .collect()
+}
+
+// Adds the price each buyer offers the first time each sequence of four changes shows up.
+fn bananas_per_sequence(secrets: &[u64], steps: usize) -> Vec<u64> {
+    let mut bananas = vec![0; SEQUENCE_COUNT];
+    let mut seen_by = vec![usize::MAX; SEQUENCE_COUNT];
+
+    for (buyer, secret) in secrets.iter().enumerate() {
+        let mut secret = *secret;
+        let mut price = (secret % 10) as i64;
+        let mut sequence = 0;
+
+        for step in 0..steps {
+            secret = next_secret(secret);
+            let next_price = (secret % 10) as i64;
+            sequence = (sequence * 19 + (next_price - price + 9) as usize) % SEQUENCE_COUNT;
+            price = next_price;
+
+            if step >= 3 && seen_by[sequence] != buyer {
+                seen_by[sequence] = buyer;
+                bananas[sequence] += price as u64;
+            }
+        }
+    }
+
+    bananas
+}
+
+fn part1(secrets: &[u64]) -> Result<u64> {
+    let result = secrets
+        .iter()
+        .map(|&secret| (0..STEPS).fold(secret, |secret, _| next_secret(secret)))
+        .sum();
+
+    Ok(result)
+}
+
+fn part2(secrets: &[u64]) -> Result<u64> {
+    let bananas = bananas_per_sequence(secrets, STEPS);
+    Ok(bananas.into_iter().max().unwrap_or(0))
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |secrets| part1(secrets))?;
+        bench(DAY, 2, parse, |secrets| part2(secrets))?;
+        return Ok(());
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(37327623, part1(&parse(TEST)?)?);
+
+    assert_eq!(
+        vec![
+            15887950, 16495136, 527345, 704524, 1553684, 12683156, 11100544, 12249484, 7753432,
+            5908254
+        ],
+        std::iter::successors(Some(123), |secret| Some(next_secret(*secret)))
+            .skip(1)
+            .take(10)
+            .collect::<Vec<_>>()
+    );
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!(23, part2(&parse(TEST_2)?)?);
+
+    // Prices of 123 change by -1, -1, 0, 2 before the buyer first offers 6 bananas.
+    let bananas = bananas_per_sequence(&[123], 10);
+    let sequence = [-1, -1, 0, 2]
+        .iter()
+        .fold(0, |s, c| s * 19 + (c + 9) as usize);
+    assert_eq!(6, bananas[sequence]);
+    assert_eq!(Some(&6), bananas.iter().max());
+
+    let result = time_snippet!(part2(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    Ok(())
+}
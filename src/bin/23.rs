@@ -0,0 +1,194 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
+use anyhow::*;
+use code_timing_macros::time_snippet;
+use const_format::concatcp;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+
+const DAY: &str = "23";
+const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+
+const TEST: &str = "\
+kh-tc
+qp-kh
+de-cg
+ka-co
+yn-aq
+qp-ub
+cg-tb
+vc-aq
+tb-ka
+wh-tc
+yn-cg
+kh-ub
+ta-co
+de-co
+tc-td
+tb-wq
+wh-td
+ta-ka
+td-qp
+aq-cg
+wq-ub
+ub-vc
+de-ta
+wq-aq
+wq-vc
+wh-yn
+ka-de
+kh-ta
+co-tc
+wh-qp
+tb-vc
+td-yn
+";
+
+struct Network {
+    names: Vec<String>,
+    links: Vec<BTreeSet<usize>>,
+}
+
+impl Network {
+    fn new(input: &str) -> Result<Network> {
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut names = vec![];
+        let mut links: Vec<BTreeSet<usize>> = vec![];
+
+        for line in input.lines() {
+            let Some((a, b)) = line.split_once('-') else {
+                bail!("Invalid link [{}]", line);
+            };
+
+            let mut id = |name: &str| {
+                *ids.entry(name.to_owned()).or_insert_with(|| {
+                    names.push(name.to_owned());
+                    links.push(BTreeSet::new());
+                    names.len() - 1
+                })
+            };
+            let (a, b) = (id(a), id(b));
+            links[a].insert(b);
+            links[b].insert(a);
+        }
+
+        Ok(Network { names, links })
+    }
+
+    // Each set of three computers linked to one another, reported once.
+    fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = vec![];
+        for a in 0..self.links.len() {
+            for &b in self.links[a].range(a + 1..) {
+                for &c in self.links[b].range(b + 1..) {
+                    if self.links[a].contains(&c) {
+                        triangles.push([a, b, c]);
+                    }
+                }
+            }
+        }
+
+        triangles
+    }
+
+    // Bron–Kerbosch with pivoting.
+    fn largest_clique(&self) -> Vec<usize> {
+        let mut best = vec![];
+        let candidates = (0..self.links.len()).collect();
+        self.extend_clique(&mut vec![], candidates, BTreeSet::new(), &mut best);
+        best
+    }
+
+    fn extend_clique(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: BTreeSet<usize>,
+        mut excluded: BTreeSet<usize>,
+        best: &mut Vec<usize>,
+    ) {
+        if candidates.is_empty() {
+            if excluded.is_empty() && clique.len() > best.len() {
+                *best = clique.clone();
+            }
+            return;
+        }
+
+        let pivot = *candidates
+            .union(&excluded)
+            .max_by_key(|v| self.links[**v].len())
+            .unwrap();
+        let to_try: Vec<usize> = candidates.difference(&self.links[pivot]).copied().collect();
+
+        for v in to_try {
+            clique.push(v);
+            self.extend_clique(
+                clique,
+                candidates.intersection(&self.links[v]).copied().collect(),
+                excluded.intersection(&self.links[v]).copied().collect(),
+                best,
+            );
+            clique.pop();
+
+            candidates.remove(&v);
+            excluded.insert(v);
+        }
+    }
+
+    fn password(&self, clique: &[usize]) -> String {
+        let mut names: Vec<&str> = clique.iter().map(|v| self.names[*v].as_str()).collect();
+        names.sort_unstable();
+        names.join(",")
+    }
+}
+
+fn parse(input: &str) -> Result<Network> {
+    Network::new(input)
+}
+
+fn part1(network: &Network) -> Result<usize> {
+    let answer = network
+        .triangles()
+        .iter()
+        .filter(|t| t.iter().any(|v| network.names[*v].starts_with('t')))
+        .count();
+
+    Ok(answer)
+}
+
+fn part2(network: &Network) -> Result<String> {
+    Ok(network.password(&network.largest_clique()))
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(7, part1(&parse(TEST)?)?);
+    assert_eq!(12, parse(TEST)?.triangles().len());
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!("co,de,ka,ta", part2(&parse(TEST)?)?);
+
+    let result = time_snippet!(part2(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
+use anyhow::*;
+use code_timing_macros::time_snippet;
+use const_format::concatcp;
+use std::collections::HashMap;
+use std::fs;
+
+const DAY: &str = "24";
+const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+
+const TEST: &str = "\
+x00: 1
+x01: 1
+x02: 1
+y00: 0
+y01: 1
+y02: 0
+
+x00 AND y00 -> z00
+x01 XOR y01 -> z01
+x02 OR y02 -> z02
+";
+const TEST_LARGE: &str = "\
+x00: 1
+x01: 0
+x02: 1
+x03: 1
+x04: 0
+y00: 1
+y01: 1
+y02: 1
+y03: 1
+y04: 1
+
+ntg XOR fgs -> mjb
+y02 OR x01 -> tnw
+kwq OR kpj -> z05
+x00 OR x03 -> fst
+tgd XOR rvg -> z01
+vdt OR tnw -> bfw
+bfw AND frj -> z10
+ffh OR nrd -> bqk
+y00 AND y03 -> djm
+y03 OR y00 -> psh
+bqk OR frj -> z08
+tnw OR fst -> frj
+gnj AND tgd -> z11
+bfw XOR mjb -> z00
+x03 OR x00 -> vdt
+gnj AND wpb -> z02
+x04 AND y00 -> kjc
+djm OR pbm -> qhw
+nrd AND vdt -> hwm
+kjc AND fst -> rvg
+y04 OR y02 -> fgs
+y01 AND x02 -> pbm
+ntg OR kjc -> kwq
+psh XOR fgs -> tgd
+qhw XOR tgd -> z09
+pbm OR djm -> kpj
+x03 XOR y03 -> ffh
+x00 XOR y04 -> ntg
+bfw OR bqk -> z06
+nrd XOR fgs -> wpb
+frj XOR qhw -> z04
+bqk OR frj -> z07
+y03 OR x01 -> nrd
+hwm AND bqk -> z03
+tgd XOR rvg -> z12
+tnw OR pbm -> gnj
+";
+// A 3 bit ripple carry adder, with the outputs of s01 and a01 swapped, and those of z02 and z03.
+const TEST_ADDER: &str = "\
+x00: 1
+x01: 1
+x02: 1
+y00: 1
+y01: 0
+y02: 1
+
+x00 XOR y00 -> z00
+x00 AND y00 -> c00
+x01 XOR y01 -> a01
+x01 AND y01 -> s01
+s01 XOR c00 -> z01
+s01 AND c00 -> b01
+a01 OR b01 -> c01
+x02 XOR y02 -> s02
+x02 AND y02 -> a02
+s02 XOR c01 -> z03
+s02 AND c01 -> b02
+a02 OR b02 -> z02
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    And,
+    Or,
+    Xor,
+}
+
+impl Operation {
+    fn parse(name: &str) -> Result<Operation> {
+        match name {
+            "AND" => Ok(Operation::And),
+            "OR" => Ok(Operation::Or),
+            "XOR" => Ok(Operation::Xor),
+            _ => bail!("Unknown gate [{}]", name),
+        }
+    }
+
+    fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Operation::And => a && b,
+            Operation::Or => a || b,
+            Operation::Xor => a ^ b,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Gate {
+    inputs: [String; 2],
+    operation: Operation,
+    output: String,
+}
+
+impl Gate {
+    fn has_input_prefix(&self, prefixes: &[char]) -> bool {
+        self.inputs.iter().all(|input| input.starts_with(prefixes))
+    }
+}
+
+struct Circuit {
+    initial: HashMap<String, bool>,
+    gates: Vec<Gate>,
+}
+
+impl Circuit {
+    fn new(input: &str) -> Result<Circuit> {
+        let mut initial = HashMap::new();
+        let mut gates = vec![];
+
+        let mut lines = input.lines();
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+
+            let Some((wire, value)) = line.split_once(": ") else {
+                bail!("Invalid wire [{}]", line);
+            };
+            let value = match value {
+                "0" => false,
+                "1" => true,
+                _ => bail!("Invalid value for wire [{}]", line),
+            };
+            initial.insert(wire.to_owned(), value);
+        }
+
+        for line in lines {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [a, operation, b, "->", output] = parts[..] else {
+                bail!("Invalid gate [{}]", line);
+            };
+            gates.push(Gate {
+                inputs: [a.to_owned(), b.to_owned()],
+                operation: Operation::parse(operation)?,
+                output: output.to_owned(),
+            });
+        }
+
+        Ok(Circuit { initial, gates })
+    }
+
+    // Fires the gates as their inputs become known, until no more can fire.
+    fn evaluate(&self) -> Result<HashMap<String, bool>> {
+        let mut values = self.initial.clone();
+        let mut pending: Vec<&Gate> = self.gates.iter().collect();
+
+        while !pending.is_empty() {
+            let count = pending.len();
+            pending.retain(|gate| {
+                let [a, b] = &gate.inputs;
+                match (values.get(a), values.get(b)) {
+                    (Some(a), Some(b)) => {
+                        let value = gate.operation.apply(*a, *b);
+                        values.insert(gate.output.clone(), value);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            ensure!(
+                pending.len() < count,
+                "Gates never get their inputs: {:?}",
+                pending
+            );
+        }
+
+        Ok(values)
+    }
+
+    fn number(values: &HashMap<String, bool>, prefix: char) -> u64 {
+        let mut bits: Vec<(&String, &bool)> = values
+            .iter()
+            .filter(|(wire, _)| wire.starts_with(prefix))
+            .collect();
+        bits.sort();
+
+        bits.iter()
+            .rev()
+            .fold(0, |number, (_, bit)| number << 1 | **bit as u64)
+    }
+
+    fn swap_outputs(&mut self, a: &str, b: &str) {
+        for gate in self.gates.iter_mut() {
+            if gate.output == a {
+                gate.output = b.to_owned();
+            } else if gate.output == b {
+                gate.output = a.to_owned();
+            }
+        }
+    }
+
+    // The circuit should be a ripple carry adder, where every bit goes through
+    //   s = x XOR y, a = x AND y, z = s XOR carry, b = s AND carry, carry' = a OR b
+    // and the last carry is the highest z. Outputs that break that shape have been swapped.
+    fn swapped_outputs(&self) -> Vec<String> {
+        let last_z = self
+            .gates
+            .iter()
+            .map(|gate| &gate.output)
+            .filter(|output| output.starts_with('z'))
+            .max();
+        let feeds = |output: &str, operation: Operation| {
+            self.gates.iter().any(|gate| {
+                gate.operation == operation && gate.inputs.iter().any(|input| input == output)
+            })
+        };
+        let first_bit = |gate: &Gate| gate.inputs.iter().any(|input| input == "x00");
+
+        let mut swapped: Vec<String> = self
+            .gates
+            .iter()
+            .filter(|gate| {
+                let from_inputs = gate.has_input_prefix(&['x', 'y']);
+                let to_z = gate.output.starts_with('z');
+                if Some(&gate.output) == last_z {
+                    return gate.operation != Operation::Or;
+                }
+
+                match gate.operation {
+                    Operation::Xor if from_inputs => {
+                        !first_bit(gate) && !feeds(&gate.output, Operation::Xor)
+                    }
+                    Operation::Xor => !to_z,
+                    Operation::And => {
+                        to_z || (!first_bit(gate) && !feeds(&gate.output, Operation::Or))
+                    }
+                    Operation::Or => to_z,
+                }
+            })
+            .map(|gate| gate.output.clone())
+            .collect();
+        swapped.sort_unstable();
+
+        swapped
+    }
+}
+
+fn parse(input: &str) -> Result<Circuit> {
+    Circuit::new(input)
+}
+
+fn part1(circuit: &Circuit) -> Result<u64> {
+    let values = circuit.evaluate()?;
+    Ok(Circuit::number(&values, 'z'))
+}
+
+fn part2(circuit: &Circuit) -> Result<String> {
+    Ok(circuit.swapped_outputs().join(","))
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        bench(DAY, 2, parse, part2)?;
+        return Ok(());
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(4, part1(&parse(TEST)?)?);
+    assert_eq!(2024, part1(&parse(TEST_LARGE)?)?);
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    //region Part 2
+    println!("\n=== Part 2 ===");
+
+    assert_eq!("a01,s01,z02,z03", part2(&parse(TEST_ADDER)?)?);
+
+    let mut adder = parse(TEST_ADDER)?;
+    let values = adder.evaluate()?;
+    assert_ne!(7 + 5, Circuit::number(&values, 'z'));
+    adder.swap_outputs("a01", "s01");
+    adder.swap_outputs("z02", "z03");
+    assert!(adder.swapped_outputs().is_empty());
+    let values = adder.evaluate()?;
+    assert_eq!(7, Circuit::number(&values, 'x'));
+    assert_eq!(5, Circuit::number(&values, 'y'));
+    assert_eq!(7 + 5, Circuit::number(&values, 'z'));
+
+    let result = time_snippet!(part2(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use adv_code_2024::bench::{bench, bench_requested};
+use adv_code_2024::*;
+use anyhow::*;
+use code_timing_macros::time_snippet;
+use const_format::concatcp;
+use std::fs;
+
+const DAY: &str = "25";
+const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
+
+const TEST: &str = "\
+#####
+.####
+.####
+.####
+.#.#.
+.#...
+.....
+
+#####
+##.##
+.#.##
+...##
+...#.
+...#.
+.....
+
+.....
+#....
+#....
+#...#
+#.#.#
+#.###
+#####
+
+.....
+.....
+#.#..
+###..
+###.#
+###.#
+#####
+
+.....
+.....
+.....
+#....
+#.#..
+#.#.#
+#####
+";
+
+// Column heights of the locks and keys, not counting the full top or bottom row.
+#[derive(Debug, Default)]
+struct Schematics {
+    locks: Vec<Vec<usize>>,
+    keys: Vec<Vec<usize>>,
+    // Room in each column, between the top and bottom rows.
+    space: usize,
+}
+
+impl Schematics {
+    fn new(input: &str) -> Result<Schematics> {
+        let mut schematics = Schematics::default();
+
+        let mut block: Vec<String> = vec![];
+        for line in input.lines().chain(std::iter::once("")) {
+            if !line.is_empty() {
+                block.push(line.to_owned());
+                continue;
+            }
+            if block.is_empty() {
+                continue;
+            }
+
+            schematics.add(&block)?;
+            block.clear();
+        }
+
+        Ok(schematics)
+    }
+
+    fn add(&mut self, block: &[String]) -> Result<()> {
+        ensure!(block.len() >= 2, "Schematic too short: {:?}", block);
+        let width = block[0].len();
+        ensure!(
+            block.iter().all(|row| row.len() == width),
+            "Schematic rows have different lengths: {:?}",
+            block
+        );
+
+        let space = block.len() - 2;
+        ensure!(
+            self.locks.is_empty() && self.keys.is_empty() || self.space == space,
+            "Schematics have different heights"
+        );
+        self.space = space;
+
+        let is_lock = block[0].bytes().all(|b| b == b'#');
+        let is_key = block[block.len() - 1].bytes().all(|b| b == b'#');
+        ensure!(
+            is_lock || is_key,
+            "Schematic is neither a lock nor a key: {:?}",
+            block
+        );
+
+        // The full top or bottom row puts at least one '#' in every column.
+        let heights = (0..width)
+            .map(|col| {
+                block
+                    .iter()
+                    .filter(|row| row.as_bytes()[col] == b'#')
+                    .count()
+                    - 1
+            })
+            .collect();
+
+        if is_lock {
+            self.locks.push(heights);
+        } else {
+            self.keys.push(heights);
+        }
+
+        Ok(())
+    }
+
+    fn fits(&self, lock: &[usize], key: &[usize]) -> bool {
+        lock.iter().zip(key).all(|(l, k)| l + k <= self.space)
+    }
+}
+
+fn parse(input: &str) -> Result<Schematics> {
+    Schematics::new(input)
+}
+
+fn part1(schematics: &Schematics) -> Result<usize> {
+    let answer = schematics
+        .locks
+        .iter()
+        .map(|lock| {
+            schematics
+                .keys
+                .iter()
+                .filter(|key| schematics.fits(lock, key))
+                .count()
+        })
+        .sum();
+
+    Ok(answer)
+}
+
+fn main() -> Result<()> {
+    start_day(DAY);
+
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, part1)?;
+        return Ok(());
+    }
+
+    //region Part 1
+    println!("=== Part 1 ===");
+
+    assert_eq!(3, part1(&parse(TEST)?)?);
+
+    let schematics = parse(TEST)?;
+    assert_eq!(
+        vec![vec![0, 5, 3, 4, 3], vec![1, 2, 0, 5, 3]],
+        schematics.locks
+    );
+    assert_eq!(
+        vec![
+            vec![5, 0, 2, 1, 3],
+            vec![4, 3, 4, 0, 2],
+            vec![3, 0, 2, 0, 1]
+        ],
+        schematics.keys
+    );
+
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
+    println!("Result = {}", result);
+    //endregion
+
+    // Day 25 has no second puzzle.
+
+    Ok(())
+}
@@ -1,9 +1,9 @@
 use anyhow::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use code_timing_macros::time_snippet;
 use const_format::concatcp;
 use adv_code_2024::*;
+use adv_code_2024::bench::{bench, bench_requested};
 
 const DAY: &str = "NN"; // TODO: Fill the day
 const INPUT_FILE: &str = concatcp!("input/", DAY, ".txt");
@@ -12,37 +12,49 @@ const TEST: &str = "\
 <TEST-INPUT>
 "; // TODO: Add the test input
 
+// TODO: Parse the input into what both parts need
+fn parse(input: &str) -> Result<Vec<String>> {
+    Ok(input.lines().map(String::from).collect())
+}
+
+fn part1(lines: &[String]) -> Result<usize> {
+    // TODO: Solve Part 1 of the puzzle
+    let answer = lines.len();
+    Ok(answer)
+}
+
+// fn part2(lines: &[String]) -> Result<usize> {
+//     Ok(0)
+// }
+
 fn main() -> Result<()> {
     start_day(DAY);
 
+    // The runner only wants the answers and timings, without the examples and checks below
+    if bench_requested() {
+        bench(DAY, 1, parse, |lines| part1(lines))?;
+        // bench(DAY, 2, parse, |lines| part2(lines))?;
+        return Ok(());
+    }
+
     //region Part 1
     println!("=== Part 1 ===");
 
-    fn part1<R: BufRead>(reader: R) -> Result<usize> {
-        // TODO: Solve Part 1 of the puzzle
-        let answer = reader.lines().flatten().count();
-        Ok(answer)
-    }
-
     // TODO: Set the expected answer for the test input
-    assert_eq!(0, part1(BufReader::new(TEST.as_bytes()))?);
+    assert_eq!(0, part1(&parse(TEST)?)?);
 
-    let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    let result = time_snippet!(part1(input_file)?);
+    let input = fs::read_to_string(INPUT_FILE)?;
+    let input = time_snippet!(parse(&input)?);
+    let result = time_snippet!(part1(&input)?);
     println!("Result = {}", result);
     //endregion
 
     //region Part 2
     // println!("\n=== Part 2 ===");
     //
-    // fn part2<R: BufRead>(reader: R) -> Result<usize> {
-    //     Ok(0)
-    // }
-    //
-    // assert_eq!(0, part2(BufReader::new(TEST.as_bytes()))?);
+    // assert_eq!(0, part2(&parse(TEST)?)?);
     //
-    // let input_file = BufReader::new(File::open(INPUT_FILE)?);
-    // let result = time_snippet!(part2(input_file)?);
+    // let result = time_snippet!(part2(&input)?);
     // println!("Result = {}", result);
     //endregion
 
@@ -0,0 +1,434 @@
+use adv_code_2024::answers::*;
+use adv_code_2024::bench::*;
+use adv_code_2024::generate::*;
+use adv_code_2024::scaffold::*;
+use adv_code_2024::*;
+use anyhow::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+const USAGE: &str = "\
+Usage: runner <command> [options]
+
+Commands:
+  run      Solve every day and part once, after `cargo build --release`
+             --days <list>        Days to run, like 1,6,9-12 (default all)
+             --format <format>    text, or json for one object per day and part (default text)
+  bench    Time parsing and solving every day and part, after `cargo build --release`
+             --runs <n>           Timed runs per part (default 10)
+             --warmup <n>         Untimed runs before those (default 3)
+             --days <list>        Days to run, like 1,6,9-12 (default all)
+             --output <file>      Where to write the report (default bench.json)
+             --baseline <file>    Previous report to compare with
+             --threshold <pct>    Slowdown of the median that counts as a regression (default 10)
+  gen <day>  Write a random input for a day, see `runner gen` for the days and sizes
+             --seed <n>           Same seed and size, same input (default 0)
+             --size <n>           Size of the input (default the size of the real one)
+             --output <file>      Where to write the input (default standard output)
+  new-day <day>  Start a day from the src/bin/NN.rs template, with an empty input and example
+  submit <day> <part> [answer]  Log a guess, by default the day's own answer, with a cooldown after
+                                wrong ones. The reply comes from the correct answer once logged.
+             --feedback <reply>   What the site said: too-high, too-low, wrong or correct
+             --log <file>         Where the guesses are kept (default answers.json)
+  answers  Show the logged guesses
+             --days <list>        Days to show, like 1,6,9-12 (default all)
+             --log <file>         Where the guesses are kept (default answers.json)
+";
+
+const DAYS: std::ops::RangeInclusive<u8> = 1..=25;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("run") => run_days(&args[1..]),
+        Some("bench") => run_bench(&args[1..]),
+        Some("gen") => run_gen(&args[1..]),
+        Some("new-day") => run_new_day(&args[1..]),
+        Some("submit") => run_submit(&args[1..]),
+        Some("answers") => run_answers(&args[1..]),
+        _ => {
+            eprint!("{}", USAGE);
+            bail!("Unknown command");
+        }
+    }
+}
+
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|position| args.get(position + 1))
+        .map(String::as_str)
+}
+
+fn parsed_option<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> Result<T> {
+    match option(args, name) {
+        None => Ok(default),
+        Some(value) => value
+            .parse()
+            .map_err(|_| anyhow!("Invalid value for {} [{}]", name, value)),
+    }
+}
+
+// Days as written in their file names, from a list like `1,6,9-12`.
+fn parse_days(list: Option<&str>) -> Result<Vec<String>> {
+    let Some(list) = list else {
+        return Ok(DAYS.map(|day| format!("{:02}", day)).collect());
+    };
+
+    let mut days = vec![];
+    for item in list.split(',') {
+        let (first, last) = item.split_once('-').unwrap_or((item, item));
+        let first: u8 = first
+            .parse()
+            .with_context(|| format!("Invalid day [{}]", item))?;
+        let last: u8 = last
+            .parse()
+            .with_context(|| format!("Invalid day [{}]", item))?;
+        ensure!(
+            DAYS.contains(&first) && DAYS.contains(&last) && first <= last,
+            "Invalid days [{}]",
+            item
+        );
+        days.extend((first..=last).map(|day| format!("{:02}", day)));
+    }
+
+    Ok(days)
+}
+
+fn format_stats(stats: &Stats) -> String {
+    format!(
+        "min {:?} median {:?} p95 {:?}",
+        Duration::from_nanos(stats.min_ns),
+        Duration::from_nanos(stats.median_ns),
+        Duration::from_nanos(stats.p95_ns)
+    )
+}
+
+// Days are separate binaries, built next to this one.
+fn day_binary(day: &str) -> Result<PathBuf> {
+    Ok(std::env::current_exe()?.with_file_name(format!("{}{}", day, std::env::consts::EXE_SUFFIX)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+fn print_report(report: &PartReport, format: Format) -> Result<()> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string(report)?),
+        Format::Text => match (report.status, &report.answer) {
+            (Status::Ok, Some(answer)) => println!(
+                "Day {} part {}: {} (parse {:?}, solve {:?})",
+                report.day,
+                report.part,
+                answer,
+                Duration::from_nanos(report.parse_ns.unwrap_or(0)),
+                Duration::from_nanos(report.solve_ns.unwrap_or(0))
+            ),
+            (status, _) => println!(
+                "Day {} part {}: {}, {}",
+                report.day,
+                report.part,
+                if status == Status::Skipped {
+                    "skipped"
+                } else {
+                    "error"
+                },
+                report.message.as_deref().unwrap_or("")
+            ),
+        },
+    }
+
+    Ok(())
+}
+
+// What the day reports with `--report`, and the status of the parts missing from it: skipped
+// when the day finished fine, like the missing part 2 of the last day, and failed otherwise.
+fn day_reports(day: &str) -> Result<(Vec<PartReport>, (Status, String))> {
+    let binary = day_binary(day)?;
+    if !Path::new(&input_path(day)).exists() {
+        return Ok((vec![], (Status::Skipped, "no input".to_owned())));
+    }
+    if !binary.exists() {
+        return Ok((vec![], (Status::Skipped, "not built".to_owned())));
+    }
+
+    let output = Command::new(&binary)
+        .arg("--report")
+        .output()
+        .with_context(|| format!("Cannot run {}", binary.display()))?;
+
+    let mut reports = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some(json) = line.strip_prefix(REPORT_PREFIX) else {
+            continue;
+        };
+        let report: PartReport = serde_json::from_str(json)
+            .with_context(|| format!("Invalid report from day {} [{}]", day, json))?;
+        reports.push(report);
+    }
+
+    if output.status.success() {
+        return Ok((reports, (Status::Skipped, "not solved".to_owned())));
+    }
+
+    // The error or panic message comes first.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr.lines().find(|line| !line.trim().is_empty());
+    Ok((
+        reports,
+        (
+            Status::Error,
+            format!("failed with {}: {}", output.status, reason.unwrap_or("")),
+        ),
+    ))
+}
+
+fn run_days(args: &[String]) -> Result<()> {
+    let format = match option(args, "--format").unwrap_or("text") {
+        "text" => Format::Text,
+        "json" => Format::Json,
+        other => bail!("Unknown format [{}]", other),
+    };
+
+    let mut failures = 0;
+    for day in parse_days(option(args, "--days"))? {
+        let (reports, missing) = day_reports(&day)?;
+
+        for part in 1..=2 {
+            let report = reports
+                .iter()
+                .find(|report| report.part == part)
+                .cloned()
+                .unwrap_or_else(|| PartReport::new(&day, part, missing.0, &missing.1));
+            if report.status == Status::Error {
+                failures += 1;
+            }
+            print_report(&report, format)?;
+        }
+    }
+
+    ensure!(failures == 0, "{} part(s) failed", failures);
+
+    Ok(())
+}
+
+fn run_bench(args: &[String]) -> Result<()> {
+    let runs: usize = parsed_option(args, "--runs", 10)?;
+    let warmup: usize = parsed_option(args, "--warmup", 3)?;
+    let threshold: f64 = parsed_option(args, "--threshold", 10.0)?;
+    let output = option(args, "--output").unwrap_or("bench.json");
+    let baseline = option(args, "--baseline")
+        .map(|path| Report::load(Path::new(path)))
+        .transpose()?;
+
+    let mut report = Report {
+        warmup,
+        measurements: vec![],
+    };
+
+    for day in parse_days(option(args, "--days"))? {
+        let binary = day_binary(&day)?;
+        if !Path::new(&input_path(&day)).exists() || !binary.exists() {
+            println!("Day {}: skipped, no input or binary", day);
+            continue;
+        }
+
+        let output = Command::new(&binary)
+            .args([
+                "--bench",
+                &runs.to_string(),
+                "--warmup",
+                &warmup.to_string(),
+            ])
+            .output()
+            .with_context(|| format!("Cannot run {}", binary.display()))?;
+        if !output.status.success() {
+            println!("Day {}: failed with {}", day, output.status);
+            continue;
+        }
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some(json) = line.strip_prefix(MEASUREMENT_PREFIX) else {
+                continue;
+            };
+            let measurement: Measurement = serde_json::from_str(json)
+                .with_context(|| format!("Invalid measurement from day {} [{}]", day, json))?;
+
+            println!(
+                "Day {} part {}: parse {}, solve {}",
+                measurement.day,
+                measurement.part,
+                format_stats(&measurement.parse),
+                format_stats(&measurement.solve)
+            );
+            report.measurements.push(measurement);
+        }
+    }
+
+    report.save(Path::new(output))?;
+    println!("Report written to {}", output);
+
+    if let Some(baseline) = baseline {
+        let regressions = report.regressions(&baseline, threshold);
+        for regression in regressions.iter() {
+            println!(
+                "Regression on day {} part {} {:?}: median {:?} -> {:?} ({:+.1}%)",
+                regression.day,
+                regression.part,
+                regression.phase,
+                Duration::from_nanos(regression.baseline_ns),
+                Duration::from_nanos(regression.current_ns),
+                regression.change
+            );
+        }
+        ensure!(
+            regressions.is_empty(),
+            "{} part(s) slower than the baseline by more than {}%",
+            regressions.len(),
+            threshold
+        );
+    }
+
+    Ok(())
+}
+
+fn run_gen(args: &[String]) -> Result<()> {
+    let Some(day) = args.first().filter(|arg| !arg.starts_with("--")) else {
+        eprintln!("Days with a generator:");
+        for generator in GENERATORS {
+            eprintln!(
+                "  {}  size in {} (real input {})",
+                generator.day, generator.size_unit, generator.default_size
+            );
+        }
+        bail!("Missing day");
+    };
+
+    let day = parse_days(Some(day))?;
+    ensure!(day.len() == 1, "Expected a single day");
+    let generator = generator(&day[0])?;
+
+    let seed: u64 = parsed_option(args, "--seed", 0)?;
+    let size: usize = parsed_option(args, "--size", generator.default_size)?;
+    let input = generator.generate(seed, size);
+
+    match option(args, "--output") {
+        None => print!("{}", input),
+        Some(path) => {
+            std::fs::write(path, input).with_context(|| format!("Cannot write {}", path))?
+        }
+    }
+
+    Ok(())
+}
+
+fn run_new_day(args: &[String]) -> Result<()> {
+    let day = parse_days(Some(args.first().context("Missing day")?))?;
+    ensure!(day.len() == 1, "Expected a single day");
+
+    for path in new_day(Path::new(""), &day[0])? {
+        println!("Created {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs())
+}
+
+fn run_submit(args: &[String]) -> Result<()> {
+    let positional: Vec<&String> = args
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .collect();
+    ensure!(
+        (2..=3).contains(&positional.len()),
+        "Expected a day, a part and maybe an answer"
+    );
+
+    let day = parse_days(Some(positional[0]))?;
+    ensure!(day.len() == 1, "Expected a single day");
+    let day = &day[0];
+    let part: u8 = positional[1]
+        .parse()
+        .ok()
+        .filter(|part| (1..=2).contains(part))
+        .with_context(|| format!("Invalid part [{}]", positional[1]))?;
+
+    let answer = match positional.get(2) {
+        Some(answer) => answer.to_string(),
+        None => {
+            let (reports, missing) = day_reports(day)?;
+            let report = reports
+                .into_iter()
+                .find(|report| report.part == part)
+                .unwrap_or_else(|| PartReport::new(day, part, missing.0, &missing.1));
+            match (report.status, report.answer) {
+                (Status::Ok, Some(answer)) => answer,
+                _ => bail!(
+                    "No answer from day {} part {}: {}",
+                    day,
+                    part,
+                    report.message.unwrap_or_default()
+                ),
+            }
+        }
+    };
+    let feedback = option(args, "--feedback")
+        .map(str::parse::<Feedback>)
+        .transpose()?;
+
+    let path = Path::new(option(args, "--log").unwrap_or("answers.json"));
+    let mut log = AnswerLog::load(path)?;
+    let submission = log.submit(day, part, &answer, feedback, now()?)?;
+    log.save(path)?;
+
+    for warning in submission.warnings.iter() {
+        println!("Warning: {}", warning);
+    }
+    println!(
+        "Day {} part {}: {} is {}",
+        day, part, answer, submission.feedback
+    );
+
+    Ok(())
+}
+
+fn run_answers(args: &[String]) -> Result<()> {
+    let log = AnswerLog::load(Path::new(option(args, "--log").unwrap_or("answers.json")))?;
+    let now = now()?;
+
+    for day in parse_days(option(args, "--days"))? {
+        for part in 1..=2 {
+            let Some(part_log) = log.part(&day, part) else {
+                continue;
+            };
+
+            let mut state = match (&part_log.correct, part_log.bounds()) {
+                (Some(correct), _) => format!("solved with {}", correct),
+                (None, (Some(high), Some(low))) => format!("between {} and {}", low, high),
+                (None, (Some(high), None)) => format!("below {}", high),
+                (None, (None, Some(low))) => format!("above {}", low),
+                (None, (None, None)) => "unsolved".to_owned(),
+            };
+            if let Some(until) = part_log.locked_until(now) {
+                state += &format!(", locked for another {}s", until - now);
+            }
+
+            println!("Day {} part {}: {}", day, part, state);
+            for guess in part_log.guesses.iter() {
+                println!("  {}: {}", guess.answer, guess.feedback);
+            }
+        }
+    }
+
+    Ok(())
+}
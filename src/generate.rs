@@ -0,0 +1,413 @@
+use anyhow::*;
+use rand::prelude::*;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+// Makes random puzzle inputs in the same format as the real ones. The same seed and size always
+// give the same input.
+pub struct Generator {
+    pub day: &'static str,
+    // What the size counts, and its value for the real input.
+    pub size_unit: &'static str,
+    pub default_size: usize,
+    generate: fn(&mut StdRng, usize) -> String,
+}
+
+impl Generator {
+    pub fn generate(&self, seed: u64, size: usize) -> String {
+        (self.generate)(&mut StdRng::seed_from_u64(seed), size)
+    }
+}
+
+pub const GENERATORS: &[Generator] = &[
+    Generator {
+        day: "09",
+        size_unit: "digits",
+        default_size: 19999,
+        generate: disk_map,
+    },
+    Generator {
+        day: "13",
+        size_unit: "machines",
+        default_size: 320,
+        generate: claw_machines,
+    },
+    Generator {
+        day: "14",
+        size_unit: "robots",
+        default_size: 500,
+        generate: robots,
+    },
+    Generator {
+        day: "15",
+        size_unit: "map side",
+        default_size: 50,
+        generate: warehouse,
+    },
+    Generator {
+        day: "16",
+        size_unit: "maze side",
+        default_size: 141,
+        generate: reindeer_maze,
+    },
+    Generator {
+        day: "17",
+        size_unit: "instructions mixing register B",
+        default_size: 4,
+        generate: program,
+    },
+    Generator {
+        day: "18",
+        size_unit: "memory side",
+        default_size: 71,
+        generate: byte_drops,
+    },
+    Generator {
+        day: "19",
+        size_unit: "towels and designs",
+        default_size: 400,
+        generate: towels,
+    },
+    Generator {
+        day: "20",
+        size_unit: "track side",
+        default_size: 141,
+        generate: race_track,
+    },
+];
+
+pub fn generator(day: &str) -> Result<&'static Generator> {
+    GENERATORS
+        .iter()
+        .find(|g| g.day == day)
+        .with_context(|| format!("No generator for day {}", day))
+}
+
+// A single line of file and free space sizes, starting and ending with a file.
+fn disk_map(rng: &mut StdRng, size: usize) -> String {
+    let digits = size.max(1) | 1;
+    let mut line: String = (0..digits)
+        .map(|i| {
+            let min = if i % 2 == 0 { 1 } else { 0 };
+            char::from(b'0' + rng.gen_range(min..=9))
+        })
+        .collect();
+    line.push('\n');
+
+    line
+}
+
+// Button moves are never parallel, and about half of the prizes can be won in 100 presses.
+fn claw_machines(rng: &mut StdRng, size: usize) -> String {
+    let mut machines = vec![];
+    while machines.len() < size {
+        let a: (u64, u64) = (rng.gen_range(10..100), rng.gen_range(10..100));
+        let b: (u64, u64) = (rng.gen_range(10..100), rng.gen_range(10..100));
+        if a.0 * b.1 == a.1 * b.0 {
+            continue;
+        }
+
+        let prize = if rng.gen_bool(0.5) {
+            let (presses_a, presses_b) = (rng.gen_range(0..=100), rng.gen_range(0..=100));
+            (
+                presses_a * a.0 + presses_b * b.0,
+                presses_a * a.1 + presses_b * b.1,
+            )
+        } else {
+            (rng.gen_range(1000..20000), rng.gen_range(1000..20000))
+        };
+
+        machines.push(format!(
+            "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+            a.0, a.1, b.0, b.1, prize.0, prize.1
+        ));
+    }
+
+    machines.join("\n")
+}
+
+// Robots in the 101 by 103 bathroom the day solves for.
+fn robots(rng: &mut StdRng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            format!(
+                "p={},{} v={},{}\n",
+                rng.gen_range(0..101),
+                rng.gen_range(0..103),
+                rng.gen_range(-99..100),
+                rng.gen_range(-99..100)
+            )
+        })
+        .collect()
+}
+
+// A walled map with scattered walls and boxes, then 8 moves per tile in lines of 1000.
+fn warehouse(rng: &mut StdRng, size: usize) -> String {
+    let side = size.max(4);
+    let mut map = vec![vec!['#'; side]; side];
+    for row in map.iter_mut().take(side - 1).skip(1) {
+        for tile in row.iter_mut().take(side - 1).skip(1) {
+            *tile = match rng.gen_range(0..100) {
+                0..5 => '#',
+                5..35 => 'O',
+                _ => '.',
+            };
+        }
+    }
+    map[rng.gen_range(1..side - 1)][rng.gen_range(1..side - 1)] = '@';
+
+    let moves: Vec<char> = (0..8 * side * side)
+        .map(|_| ['^', 'v', '<', '>'][rng.gen_range(0..4)])
+        .collect();
+
+    let mut input = grid_to_string(&map);
+    input.push('\n');
+    for line in moves.chunks(1000) {
+        input.extend(line);
+        input.push('\n');
+    }
+
+    input
+}
+
+// A maze with loops, starting bottom left and ending top right.
+fn reindeer_maze(rng: &mut StdRng, size: usize) -> String {
+    let mut open = maze(rng, size);
+    let side = open.len();
+
+    // Knock down some walls between corridors so there is more than one way through.
+    for row in 1..side - 1 {
+        for col in 1..side - 1 {
+            let between = (open[row - 1][col] && open[row + 1][col])
+                || (open[row][col - 1] && open[row][col + 1]);
+            if !open[row][col] && between && rng.gen_bool(0.1) {
+                open[row][col] = true;
+            }
+        }
+    }
+
+    let mut map = tiles(&open);
+    map[side - 2][1] = 'S';
+    map[1][side - 2] = 'E';
+
+    grid_to_string(&map)
+}
+
+// A single track from start to end, winding through a maze.
+fn race_track(rng: &mut StdRng, size: usize) -> String {
+    let open = maze(rng, size);
+    let side = open.len();
+    let start = (side - 2, 1);
+    let end = (1, side - 2);
+
+    let mut track = vec![vec![false; side]; side];
+    for (row, col) in path(&open, start, end) {
+        track[row][col] = true;
+    }
+
+    let mut map = tiles(&track);
+    map[start.0][start.1] = 'S';
+    map[end.0][end.1] = 'E';
+
+    grid_to_string(&map)
+}
+
+// A loop shaped like the real programs: B takes the low bits of A and goes through `size`
+// instructions mixing it, one of them setting C to A shifted by B, and is printed while A loses
+// three bits per turn. B stays below 8 until C is set, so the shift is never too large.
+fn program(rng: &mut StdRng, size: usize) -> String {
+    let mixing = size.max(1);
+    let shift_at = rng.gen_range(0..mixing);
+    let mut body: Vec<[u8; 2]> = (0..mixing)
+        .map(|i| match i.cmp(&shift_at) {
+            Ordering::Less => [1, rng.gen_range(0..8)],
+            Ordering::Equal => [7, 5],
+            Ordering::Greater if rng.gen_bool(0.5) => [1, rng.gen_range(0..8)],
+            Ordering::Greater => [4, rng.gen_range(0..8)],
+        })
+        .collect();
+    body.insert(rng.gen_range(0..=body.len()), [0, 3]);
+
+    let program = std::iter::once([2, 4])
+        .chain(body)
+        .chain([[5, 5], [3, 0]])
+        .flatten()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "Register A: {}\nRegister B: 0\nRegister C: 0\n\nProgram: {}\n",
+        rng.gen_range(1..1u64 << 48),
+        program
+    )
+}
+
+// Every position but the corners drops once. Those off a random path go first, so the exit
+// stays reachable until most of memory is corrupted.
+fn byte_drops(rng: &mut StdRng, size: usize) -> String {
+    let side = size.max(2);
+
+    let mut on_path = vec![vec![false; side]; side];
+    let (mut x, mut y) = (0, 0);
+    while (x, y) != (side - 1, side - 1) {
+        if y == side - 1 || (x < side - 1 && rng.gen_bool(0.5)) {
+            x += 1;
+        } else {
+            y += 1;
+        }
+        on_path[y][x] = true;
+    }
+
+    let (mut late, mut early): (Vec<_>, Vec<_>) = (0..side)
+        .flat_map(|y| (0..side).map(move |x| (x, y)))
+        .filter(|&position| position != (0, 0) && position != (side - 1, side - 1))
+        .partition(|&(x, y)| on_path[y][x]);
+    early.shuffle(rng);
+    late.shuffle(rng);
+
+    early
+        .into_iter()
+        .chain(late)
+        .map(|(x, y)| format!("{},{}\n", x, y))
+        .collect()
+}
+
+// Towels of 1 to 8 stripes, none ending with one of the colors. Half of the designs are made of
+// towels, the other half end with that color and cannot be made.
+fn towels(rng: &mut StdRng, size: usize) -> String {
+    const COLORS: [char; 5] = ['w', 'u', 'b', 'r', 'g'];
+    let never_last = COLORS[rng.gen_range(0..COLORS.len())];
+
+    let mut towels = vec![];
+    let mut seen = HashSet::new();
+    // There are only so many distinct short towels, so give up after enough repeats.
+    for _ in 0..size.max(1) * 10 {
+        if towels.len() == size.max(1) {
+            break;
+        }
+        let length = rng.gen_range(1..=8);
+        let towel: String = (0..length)
+            .map(|_| COLORS[rng.gen_range(0..COLORS.len())])
+            .collect();
+        if !towel.ends_with(never_last) && seen.insert(towel.clone()) {
+            towels.push(towel);
+        }
+    }
+
+    let designs: Vec<String> = (0..size)
+        .map(|_| {
+            let mut design = String::new();
+            let length = rng.gen_range(20..=60);
+            while design.len() < length {
+                design.push_str(&towels[rng.gen_range(0..towels.len())]);
+            }
+            if rng.gen_bool(0.5) {
+                design.push(never_last);
+            }
+            design
+        })
+        .collect();
+
+    format!("{}\n\n{}\n", towels.join(", "), designs.join("\n"))
+}
+
+// A maze without loops, with corridors on odd rows and columns and walls all around.
+fn maze(rng: &mut StdRng, size: usize) -> Vec<Vec<bool>> {
+    let side = size.max(5) | 1;
+    let mut open = vec![vec![false; side]; side];
+
+    open[1][1] = true;
+    let mut stack = vec![(1, 1)];
+    while let Some(&(row, col)) = stack.last() {
+        let mut next: Vec<(usize, usize)> = [(0, 2), (2, 0), (0, -2), (-2, 0)]
+            .iter()
+            .map(|(dr, dc)| ((row as isize + dr) as usize, (col as isize + dc) as usize))
+            .filter(|&(r, c)| r > 0 && r < side - 1 && c > 0 && c < side - 1 && !open[r][c])
+            .collect();
+
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        next.shuffle(rng);
+        let (r, c) = next[0];
+        open[(row + r) / 2][(col + c) / 2] = true;
+        open[r][c] = true;
+        stack.push((r, c));
+    }
+
+    open
+}
+
+// The positions from start to end, walking open positions only.
+fn path(open: &[Vec<bool>], start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut previous = vec![vec![None; open[0].len()]; open.len()];
+    let mut queue = std::collections::VecDeque::from([start]);
+    previous[start.0][start.1] = Some(start);
+
+    while let Some((row, col)) = queue.pop_front() {
+        if (row, col) == end {
+            break;
+        }
+        for (r, c) in [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ] {
+            if open[r][c] && previous[r][c].is_none() {
+                previous[r][c] = Some((row, col));
+                queue.push_back((r, c));
+            }
+        }
+    }
+
+    let mut path = vec![end];
+    while let Some(&position) = path.last() {
+        if position == start {
+            break;
+        }
+        path.push(previous[position.0][position.1].expect("Maze is connected"));
+    }
+
+    path
+}
+
+fn tiles(open: &[Vec<bool>]) -> Vec<Vec<char>> {
+    open.iter()
+        .map(|row| row.iter().map(|&o| if o { '.' } else { '#' }).collect())
+        .collect()
+}
+
+fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_input() {
+        for generator in GENERATORS {
+            assert_eq!(generator.generate(7, 20), generator.generate(7, 20));
+            assert_ne!(generator.generate(7, 20), generator.generate(8, 20));
+        }
+    }
+
+    #[test]
+    fn race_track_is_a_single_path() {
+        let track = generator("20").unwrap().generate(1, 21);
+        let open = track.chars().filter(|c| *c != '#' && *c != '\n').count();
+
+        let grid: Vec<Vec<bool>> = track
+            .lines()
+            .map(|line| line.chars().map(|c| c != '#').collect())
+            .collect();
+        assert_eq!(open, path(&grid, (19, 1), (1, 19)).len());
+    }
+}
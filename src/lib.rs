@@ -1,9 +1,20 @@
+pub mod answers;
+pub mod bench;
+pub mod generate;
+pub mod render;
+pub mod scaffold;
+pub mod terminal;
+
 pub fn start_day(day: &str) {
     println!("Advent of Code 2024 - Day {:0>2}", day);
 }
 
 // Additional common functions
 
+pub fn input_path(day: &str) -> String {
+    format!("input/{}.txt", day)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,152 @@
+use anyhow::*;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// Grayscale image, one byte per pixel, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Pgm,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn parse(name: &str) -> Result<ImageFormat> {
+        match name {
+            "pgm" => Ok(ImageFormat::Pgm),
+            "png" => Ok(ImageFormat::Png),
+            _ => bail!("Unknown image format [{}]", name),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Frame {
+        Frame {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    // Counts the points falling on each pixel and scales the counts so the densest pixel is white.
+    pub fn from_density(
+        width: usize,
+        height: usize,
+        points: impl Iterator<Item = (usize, usize)>,
+    ) -> Frame {
+        let mut counts = vec![0usize; width * height];
+        for (x, y) in points {
+            counts[y * width + x] += 1;
+        }
+
+        let max = counts.iter().copied().max().unwrap_or(0).max(1);
+
+        Frame {
+            width,
+            height,
+            pixels: counts.iter().map(|c| (c * 255 / max) as u8).collect(),
+        }
+    }
+
+    pub fn write(&self, path: &Path, format: ImageFormat) -> Result<()> {
+        match format {
+            ImageFormat::Pgm => self.write_pgm(path),
+            ImageFormat::Png => self.write_png(path),
+        }
+    }
+
+    pub fn write_pgm(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
+
+// Writes the frames as a looping animation. Frames are encoded as they are produced, so long
+// ranges don't need to be kept in memory.
+pub fn write_gif(
+    path: &Path,
+    width: usize,
+    height: usize,
+    delay_ms: u16,
+    frames: impl Iterator<Item = Frame>,
+) -> Result<()> {
+    let palette: Vec<u8> = (0..=255u8).flat_map(|v| [v, v, v]).collect();
+
+    let gif_width = u16::try_from(width).context("Frames are too wide for a GIF")?;
+    let gif_height = u16::try_from(height).context("Frames are too tall for a GIF")?;
+
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(writer, gif_width, gif_height, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for frame in frames {
+        ensure!(
+            frame.width == width && frame.height == height,
+            "Frame is {}x{}, expected {}x{}",
+            frame.width,
+            frame.height,
+            width,
+            height
+        );
+
+        encoder.write_frame(&gif::Frame {
+            width: gif_width,
+            height: gif_height,
+            delay: delay_ms / 10,
+            buffer: Cow::Borrowed(&frame.pixels),
+            ..gif::Frame::default()
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_scales_to_densest_pixel() {
+        let frame = Frame::from_density(2, 2, [(0, 0), (0, 0), (1, 1)].into_iter());
+        assert_eq!(vec![255, 0, 0, 127], frame.pixels);
+    }
+
+    #[test]
+    fn gif_size_must_fit_in_16_bits() {
+        let path = Path::new("never-written.gif");
+        assert!(write_gif(path, 70000, 1, 100, std::iter::empty()).is_err());
+        assert!(write_gif(path, 1, 70000, 100, std::iter::empty()).is_err());
+        assert!(!path.exists());
+    }
+}
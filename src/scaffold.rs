@@ -0,0 +1,114 @@
+use crate::input_path;
+use anyhow::{ensure, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The template is a day of its own, so it keeps building as the repo changes.
+const TEMPLATE: &str = include_str!("bin/NN.rs");
+
+const TEMPLATE_DAY: &str = "const DAY: &str = \"NN\"; // TODO: Fill the day";
+
+const TEMPLATE_TEST: &str = "\
+const TEST: &str = \"\\
+<TEST-INPUT>
+\"; // TODO: Add the test input";
+
+const TEST_STUB: &str = "
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part1_example() {
+        // TODO: Set the expected answer for the example, as in main
+        assert_eq!(0, part1(&parse(TEST).unwrap()).unwrap());
+    }
+}
+";
+
+pub fn source_path(day: &str) -> String {
+    format!("src/bin/{}.rs", day)
+}
+
+pub fn example_path(day: &str) -> String {
+    format!("input/{}.example.txt", day)
+}
+
+// The day's source from the template, reading its example from the example file.
+pub fn render_day(day: &str) -> Result<String> {
+    ensure!(
+        TEMPLATE.contains(TEMPLATE_DAY) && TEMPLATE.contains(TEMPLATE_TEST),
+        "The template no longer has the day and test input placeholders"
+    );
+
+    let source = TEMPLATE
+        .replace(TEMPLATE_DAY, &format!("const DAY: &str = \"{}\";", day))
+        .replace(
+            TEMPLATE_TEST,
+            &format!(
+                "// TODO: Add the test input to {}\nconst TEST: &str = include_str!(\"../../{}\");",
+                example_path(day),
+                example_path(day)
+            ),
+        );
+
+    Ok(source + TEST_STUB)
+}
+
+// Writes the day's source, input and example under `root`, returning the files it created. The
+// runner finds days by the name of their binary, so there's nothing else to register.
+pub fn new_day(root: &Path, day: &str) -> Result<Vec<PathBuf>> {
+    let source = root.join(source_path(day));
+    ensure!(!source.exists(), "{} already exists", source.display());
+
+    let mut created = vec![];
+    for path in [input_path(day), example_path(day)] {
+        let path = root.join(path);
+        if !path.exists() {
+            fs::write(&path, "").with_context(|| format!("Cannot write {}", path.display()))?;
+            created.push(path);
+        }
+    }
+
+    fs::write(&source, render_day(day)?)
+        .with_context(|| format!("Cannot write {}", source.display()))?;
+    created.push(source);
+
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_is_filled_in() {
+        let source = render_day("26").unwrap();
+        assert!(source.contains("const DAY: &str = \"26\";"));
+        assert!(source.contains("include_str!(\"../../input/26.example.txt\")"));
+        assert!(source.contains("fn part1_example()"));
+        assert!(!source.contains("\"NN\""));
+        assert!(!source.contains("<TEST-INPUT>"));
+    }
+
+    #[test]
+    fn new_day_keeps_existing_files() {
+        let root = std::env::temp_dir().join(format!("scaffold-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::create_dir_all(root.join("input")).unwrap();
+        fs::write(root.join(input_path("26")), "1 2 3\n").unwrap();
+
+        let created = new_day(&root, "26").unwrap();
+        assert_eq!(
+            vec![root.join(example_path("26")), root.join(source_path("26"))],
+            created
+        );
+        assert_eq!(
+            "1 2 3\n",
+            fs::read_to_string(root.join(input_path("26"))).unwrap()
+        );
+        assert!(new_day(&root, "26").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
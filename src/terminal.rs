@@ -0,0 +1,416 @@
+use anyhow::{ensure, Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::io::{stdout, BufWriter, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+pub use crossterm::style::Color;
+
+const KEYS: &str = "space pause, n step, +/- speed, arrows scroll, f follow, q quit";
+const MIN_FPS: f64 = 0.25;
+const MAX_FPS: f64 = 1000.0;
+
+// One grid cell as drawn on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: char,
+    pub color: Color,
+}
+
+impl Cell {
+    pub fn new(glyph: char, color: Color) -> Cell {
+        Cell { glyph, color }
+    }
+}
+
+// A grid that can be drawn, usually the state of a simulation.
+pub trait Scene {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn cell(&self, x: usize, y: usize) -> Cell;
+
+    // Shown under the grid.
+    fn status(&self) -> String {
+        String::new()
+    }
+
+    // The cell kept in sight while the viewport follows the scene, like a robot moving around.
+    fn focus(&self) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+// The whole grid as text, without colours.
+pub fn plain_frame(scene: &impl Scene) -> String {
+    let mut frame = String::new();
+    for y in 0..scene.height() {
+        frame.extend((0..scene.width()).map(|x| scene.cell(x, y).glyph));
+        frame.push('\n');
+    }
+
+    let status = scene.status();
+    if !status.is_empty() {
+        frame.push_str(&status);
+        frame.push('\n');
+    }
+
+    frame
+}
+
+pub fn print_frame(scene: &impl Scene) {
+    print!("{}", plain_frame(scene));
+}
+
+// Frames per second given with `--animate`, if the flag is there.
+pub fn fps_from_args(args: &[String]) -> Result<Option<f64>> {
+    let Some(position) = args.iter().position(|arg| arg == "--animate") else {
+        return Ok(None);
+    };
+
+    let fps = args
+        .get(position + 1)
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|fps| *fps > 0.0)
+        .context("Expected frames per second like --animate 30")?;
+
+    Ok(Some(fps))
+}
+
+// The part of the grid that fits on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    // Fits the viewport in `space` cells, without going past the grid.
+    fn resize(&mut self, space: (usize, usize), grid: (usize, usize)) {
+        self.width = space.0.min(grid.0);
+        self.height = space.1.min(grid.1);
+        self.scroll(0, 0, grid);
+    }
+
+    fn scroll(&mut self, dx: isize, dy: isize, grid: (usize, usize)) {
+        self.x = self
+            .x
+            .saturating_add_signed(dx)
+            .min(grid.0.saturating_sub(self.width));
+        self.y = self
+            .y
+            .saturating_add_signed(dy)
+            .min(grid.1.saturating_sub(self.height));
+    }
+
+    // Scrolls as little as possible to bring the cell in sight.
+    fn follow(&mut self, (x, y): (usize, usize), grid: (usize, usize)) {
+        if x < self.x {
+            self.x = x;
+        } else if x >= self.x + self.width {
+            self.x = x + 1 - self.width;
+        }
+        if y < self.y {
+            self.y = y;
+        } else if y >= self.y + self.height {
+            self.y = y + 1 - self.height;
+        }
+        self.scroll(0, 0, grid);
+    }
+}
+
+enum Input {
+    Advance,
+    Redraw,
+    Quit,
+}
+
+// Plays a simulation in place on the terminal, one step per frame. `step` advances the scene and
+// returns false once there is nothing left to do, which leaves the last frame on screen until
+// the animation is quit.
+pub struct Animation {
+    fps: f64,
+    paused: bool,
+    follow: bool,
+    steps: usize,
+    viewport: Viewport,
+}
+
+impl Animation {
+    pub fn new(fps: f64) -> Animation {
+        Animation {
+            fps: fps.clamp(MIN_FPS, MAX_FPS),
+            paused: false,
+            follow: true,
+            steps: 0,
+            viewport: Viewport::default(),
+        }
+    }
+
+    pub fn run<S: Scene>(
+        &mut self,
+        scene: &mut S,
+        mut step: impl FnMut(&mut S) -> bool,
+    ) -> Result<()> {
+        ensure!(stdout().is_terminal(), "Animations need a terminal");
+
+        let _terminal = RawTerminal::enter()?;
+        let mut out = BufWriter::new(stdout());
+        let mut finished = false;
+        let mut next_frame = Instant::now();
+
+        loop {
+            let (columns, rows) = terminal::size()?;
+            let grid = (scene.width(), scene.height());
+            self.viewport
+                .resize((columns as usize, (rows as usize).saturating_sub(1)), grid);
+            if let Some(focus) = scene.focus().filter(|_| self.follow) {
+                self.viewport.follow(focus, grid);
+            }
+            self.draw(&mut out, scene, finished, columns as usize)?;
+
+            match self.wait(finished, next_frame, grid)? {
+                Input::Quit => return Ok(()),
+                Input::Redraw => {}
+                Input::Advance => {
+                    if step(scene) {
+                        self.steps += 1;
+                    } else {
+                        finished = true;
+                    }
+                    next_frame = Instant::now() + Duration::from_secs_f64(1.0 / self.fps);
+                }
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        out: &mut impl Write,
+        scene: &impl Scene,
+        finished: bool,
+        columns: usize,
+    ) -> Result<()> {
+        let Viewport {
+            x,
+            y,
+            width,
+            height,
+        } = self.viewport;
+
+        // Colours only change between cells of a different kind, which keeps frames small.
+        let mut color = None;
+        for row in 0..height {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            for col in 0..width {
+                let cell = scene.cell(x + col, y + row);
+                if color != Some(cell.color) {
+                    queue!(out, SetForegroundColor(cell.color))?;
+                    color = Some(cell.color);
+                }
+                queue!(out, Print(cell.glyph))?;
+            }
+            queue!(out, terminal::Clear(ClearType::UntilNewLine))?;
+        }
+
+        let state = match (finished, self.paused) {
+            (true, _) => "done",
+            (false, true) => "paused",
+            (false, false) => "playing",
+        };
+        let status = format!(
+            "{} | step {} | {} at {} fps | view {},{} of {}x{} | {}",
+            scene.status(),
+            self.steps,
+            state,
+            self.fps,
+            x,
+            y,
+            scene.width(),
+            scene.height(),
+            KEYS
+        );
+        queue!(
+            out,
+            ResetColor,
+            cursor::MoveTo(0, height as u16),
+            terminal::Clear(ClearType::FromCursorDown),
+            Print(
+                status
+                    .trim_start_matches(" | ")
+                    .chars()
+                    .take(columns)
+                    .collect::<String>()
+            )
+        )?;
+        out.flush()?;
+
+        Ok(())
+    }
+
+    // Waits for the next frame while handling keys. A paused or finished animation only moves on
+    // with a key.
+    fn wait(&mut self, finished: bool, next_frame: Instant, grid: (usize, usize)) -> Result<Input> {
+        loop {
+            if !self.paused && !finished {
+                let left = next_frame.saturating_duration_since(Instant::now());
+                if !event::poll(left)? {
+                    return Ok(Input::Advance);
+                }
+            }
+
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    if let Some(input) = self.on_key(key, finished, grid) {
+                        return Ok(input);
+                    }
+                }
+                Event::Resize(_, _) => return Ok(Input::Redraw),
+                _ => {}
+            }
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent, finished: bool, grid: (usize, usize)) -> Option<Input> {
+        let page = (self.viewport.width as isize, self.viewport.height as isize);
+        let scroll = match key.code {
+            KeyCode::Left | KeyCode::Char('h') => Some((-1, 0)),
+            KeyCode::Right | KeyCode::Char('l') => Some((1, 0)),
+            KeyCode::Up | KeyCode::Char('k') => Some((0, -1)),
+            KeyCode::Down | KeyCode::Char('j') => Some((0, 1)),
+            KeyCode::Char('H') => Some((-page.0, 0)),
+            KeyCode::Char('L') => Some((page.0, 0)),
+            KeyCode::Char('K') => Some((0, -page.1)),
+            KeyCode::Char('J') => Some((0, page.1)),
+            _ => None,
+        };
+        // Scrolling by hand stops following the scene, until `f` is pressed.
+        if let Some((dx, dy)) = scroll {
+            self.viewport.scroll(dx, dy, grid);
+            self.follow = false;
+            return Some(Input::Redraw);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Input::Quit),
+            // Raw mode turns Ctrl+C into a key press.
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(Input::Quit)
+            }
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') if self.paused && !finished => return Some(Input::Advance),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.fps = (self.fps * 2.0).min(MAX_FPS),
+            KeyCode::Char('-') => self.fps = (self.fps / 2.0).max(MIN_FPS),
+            KeyCode::Char('f') => self.follow = true,
+            _ => return None,
+        }
+
+        Some(Input::Redraw)
+    }
+}
+
+// Raw mode on the alternate screen, put back as it was when dropped, also after errors.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> Result<RawTerminal> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            stdout(),
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Board(Vec<&'static str>);
+
+    impl Scene for Board {
+        fn width(&self) -> usize {
+            self.0[0].len()
+        }
+
+        fn height(&self) -> usize {
+            self.0.len()
+        }
+
+        fn cell(&self, x: usize, y: usize) -> Cell {
+            Cell::new(self.0[y].as_bytes()[x] as char, Color::Grey)
+        }
+
+        fn status(&self) -> String {
+            "Board".to_string()
+        }
+    }
+
+    #[test]
+    fn plain_frame_has_every_row_and_the_status() {
+        assert_eq!("#.\n.#\nBoard\n", plain_frame(&Board(vec!["#.", ".#"])));
+    }
+
+    #[test]
+    fn fps_comes_after_the_flag() {
+        let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(Some(12.5), fps_from_args(&args("--animate 12.5")).unwrap());
+        assert_eq!(None, fps_from_args(&args("--trace 1,2")).unwrap());
+        assert!(fps_from_args(&args("--animate")).is_err());
+        assert!(fps_from_args(&args("--animate 0")).is_err());
+    }
+
+    #[test]
+    fn viewport_follows_focus_inside_the_grid() {
+        let grid = (10, 8);
+        let mut viewport = Viewport::default();
+        viewport.resize((4, 3), grid);
+
+        viewport.follow((6, 5), grid);
+        assert_eq!((3, 3), (viewport.x, viewport.y));
+
+        viewport.follow((1, 4), grid);
+        assert_eq!((1, 3), (viewport.x, viewport.y));
+
+        viewport.scroll(100, 100, grid);
+        assert_eq!((6, 5), (viewport.x, viewport.y));
+
+        viewport.resize((20, 20), grid);
+        assert_eq!(
+            (0, 0, 10, 8),
+            (viewport.x, viewport.y, viewport.width, viewport.height)
+        );
+    }
+
+    #[test]
+    fn frame_shows_only_the_viewport() {
+        let board = Board(vec!["abcd", "efgh", "ijkl"]);
+        let mut animation = Animation::new(10.0);
+        animation.viewport.resize((2, 2), (4, 3));
+        animation.viewport.scroll(1, 1, (4, 3));
+
+        let mut out = vec![];
+        animation.draw(&mut out, &board, false, 80).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let (grid, status) = out.split_once("Board").unwrap();
+
+        assert!(grid.contains("fg") && grid.contains("jk"));
+        assert!(!grid.contains('a') && !grid.contains('h'));
+        assert!(status.starts_with(" | step 0 | playing at 10 fps | view 1,1 of 4x3"));
+    }
+}